@@ -0,0 +1,84 @@
+//! Updates many animated entities at once. Entities are animated in parallel on the compute task pool, which uses every core by default;
+//! set `ANIMATION_BENCH_THREADS` to limit the number of threads to measure how the systems scale, e.g.
+//! `ANIMATION_BENCH_THREADS=1 cargo bench --bench animation`.
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::tasks::{ComputeTaskPool, TaskPoolBuilder};
+use bevy_animation_state_machine::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+struct State(usize);
+
+const STATES: usize = 8;
+
+/// a ring of states that each loop until the next state is desired.
+fn animation_graph() -> SpriteAnimationGraph<State> {
+    let mut builder = SpriteAnimationGraphBuilder::new();
+    for i in 0..STATES {
+        let segment_data = SegmentData {
+            segment_frames: SegmentInterval::new(i * 8, 8).into(),
+            segment_behaviour: SegmentBehaviour::Forward,
+            texture_atlas_handle: Handle::default(),
+        };
+        builder = builder
+            .state(State(i), segment_data)
+            .transition(State(i), State(i), TransitionData::wait())
+            .transition(State(i), State((i + 1) % STATES), TransitionData::wait());
+    }
+    return builder.build().unwrap();
+}
+
+/// a world with `entities` animated sprites, and the systems of the [`SpriteAnimationPlugin`] that animate them every frame.
+fn world(entities: usize) -> (World, Schedule) {
+    let mut world = World::new();
+    world.insert_resource(animation_graph());
+    world.insert_resource(Time::default());
+    world.init_resource::<Events<AnimationPathError<State>>>();
+    for i in 0..entities {
+        world.spawn((
+            State(i % STATES),
+            SpriteAnimation::new(i % STATES, 1. / 60.),
+            TextureAtlasSprite::default(),
+            Handle::<TextureAtlas>::default(),
+        ));
+    }
+    let mut schedule = Schedule::new();
+    schedule.add_systems(
+        (
+            plan_paths::<State>,
+            advance_frames::<State>,
+            apply_frames::<State, SpriteTarget>,
+        )
+            .chain(),
+    );
+    return (world, schedule);
+}
+
+fn update(c: &mut Criterion) {
+    let threads = std::env::var("ANIMATION_BENCH_THREADS")
+        .ok()
+        .and_then(|threads| threads.parse().ok())
+        .unwrap_or_else(bevy::tasks::available_parallelism);
+    ComputeTaskPool::init(|| TaskPoolBuilder::new().num_threads(threads).build());
+
+    let mut group = c.benchmark_group(format!("update ({} threads)", threads));
+    for entities in [1_000, 5_000, 20_000] {
+        let (mut world, mut schedule) = world(entities);
+        let mut now = Instant::now();
+        group.throughput(Throughput::Elements(entities as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(entities), &entities, |b, _| {
+            b.iter(|| {
+                // a frame of every animation elapses with every update
+                now += Duration::from_secs_f32(1. / 60.);
+                world.resource_mut::<Time>().update_with_instant(now);
+                schedule.run(&mut world);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, update);
+criterion_main!(benches);
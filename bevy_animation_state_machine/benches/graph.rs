@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+use bevy_animation_state_machine::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+struct State(usize);
+
+const STATES: usize = 64;
+
+/// the graph that animation graphs were stored in before edges and vertices were indexed, kept as a baseline.
+struct HashMapGraph<V, E> {
+    edges: HashMap<(usize, usize), E>,
+    verts: HashMap<usize, V>,
+    adjacency: HashMap<usize, HashSet<usize>>,
+}
+
+impl<V, E> HashMapGraph<V, E> {
+    fn new() -> Self {
+        return HashMapGraph {
+            edges: HashMap::new(),
+            verts: HashMap::new(),
+            adjacency: HashMap::new(),
+        };
+    }
+
+    fn get_vertex_metadata(&self, vertex: usize) -> Option<&V> {
+        return self.verts.get(&vertex);
+    }
+
+    fn get_edge_metadata(&self, edge: (usize, usize)) -> Option<&E> {
+        return self.edges.get(&edge);
+    }
+
+    fn add_node(&mut self, node: usize, metadata: V) {
+        self.verts.insert(node, metadata);
+        self.adjacency.insert(node, HashSet::new());
+    }
+
+    fn add_edge(&mut self, edge: (usize, usize), metadata: E) {
+        self.edges.insert(edge, metadata);
+        self.adjacency.get_mut(&edge.0).unwrap().insert(edge.1);
+    }
+
+    fn shortest_path(&self, start: usize, end: usize) -> Option<Vec<usize>> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        let mut predecessors: HashMap<usize, usize> = HashMap::new();
+        visited.insert(start);
+        queue.push_back(start);
+        while let Some(current) = queue.pop_front() {
+            if current == end {
+                let mut path = vec![end];
+                while let Some(&node) = predecessors.get(&path[path.len() - 1]) {
+                    path.push(node);
+                }
+                return Some(path);
+            }
+            for &neighbor in &self.adjacency[&current] {
+                if visited.insert(neighbor) {
+                    queue.push_back(neighbor);
+                    predecessors.insert(neighbor, current);
+                }
+            }
+        }
+        return None;
+    }
+}
+
+fn segment_data() -> SegmentData {
+    return SegmentData {
+        segment_frames: SegmentInterval::new(0, 8).into(),
+        segment_behaviour: SegmentBehaviour::Forward,
+        texture_atlas_handle: Handle::default(),
+    };
+}
+
+/// a ring of states, each with a self-transition and a shortcut half way around the ring.
+fn transitions(i: usize) -> [(usize, TransitionData); 3] {
+    return [
+        (i, TransitionData::wait()),
+        ((i + 1) % STATES, TransitionData::wait()),
+        ((i + STATES / 2) % STATES, TransitionData::immediate()),
+    ];
+}
+
+fn animation_graph() -> SpriteAnimationGraph<State> {
+    let mut builder = SpriteAnimationGraphBuilder::new();
+    for i in 0..STATES {
+        builder = builder.state(State(i), segment_data());
+        for (to, transition_data) in transitions(i) {
+            builder = builder.transition(State(i), State(to), transition_data);
+        }
+    }
+    return builder.build().unwrap();
+}
+
+/// the same ring as [`animation_graph`], with vertex `i` for `State(i)`.
+fn hashmap_graph() -> HashMapGraph<SegmentData, TransitionData> {
+    let mut graph = HashMapGraph::new();
+    for i in 0..STATES {
+        graph.add_node(i, segment_data());
+    }
+    for i in 0..STATES {
+        for (to, transition_data) in transitions(i) {
+            graph.add_edge((i, to), transition_data);
+        }
+    }
+    return graph;
+}
+
+fn lookups(c: &mut Criterion) {
+    let animation_graph = animation_graph();
+    let hashmap_graph = hashmap_graph();
+    let vertices: Vec<_> = (0..STATES)
+        .map(|i| animation_graph.get_vertex(&State(i)))
+        .collect();
+
+    let mut group = c.benchmark_group("get_segment_data");
+    group.bench_function("graph", |b| {
+        b.iter(|| {
+            for vertex in &vertices {
+                black_box(animation_graph.get_segment_data(black_box(*vertex)));
+            }
+        })
+    });
+    group.bench_function("hashmap", |b| {
+        b.iter(|| {
+            for vertex in &vertices {
+                black_box(hashmap_graph.get_vertex_metadata(black_box(*vertex)));
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("get_transition_data");
+    group.bench_function("graph", |b| {
+        b.iter(|| {
+            for vertex in &vertices {
+                let next = (vertex + 1) % STATES;
+                black_box(animation_graph.get_transition_data(black_box((*vertex, next))));
+                black_box(animation_graph.get_transition_data(black_box((*vertex, *vertex))));
+            }
+        })
+    });
+    group.bench_function("hashmap", |b| {
+        b.iter(|| {
+            for vertex in &vertices {
+                let next = (vertex + 1) % STATES;
+                black_box(hashmap_graph.get_edge_metadata(black_box((*vertex, next))));
+                black_box(hashmap_graph.get_edge_metadata(black_box((*vertex, *vertex))));
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("shortest_path");
+    group.bench_function("graph", |b| {
+        b.iter(|| {
+            for vertex in &vertices {
+                let desired = (vertex + STATES / 4) % STATES;
+                black_box(animation_graph.shortest_path(black_box(*vertex), black_box(desired)));
+            }
+        })
+    });
+    group.bench_function("hashmap", |b| {
+        b.iter(|| {
+            for vertex in &vertices {
+                let desired = (vertex + STATES / 4) % STATES;
+                black_box(hashmap_graph.shortest_path(black_box(*vertex), black_box(desired)));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, lookups);
+criterion_main!(benches);
@@ -7,25 +7,100 @@ use indexmap::IndexMap;
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::graph::{Edge, Graph, Vertex};
+use crate::graph::{Edge, Graph, Priority, Vertex};
 use crate::SpriteAnimationState;
 use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
 
-#[derive(Default, Debug, Clone, Eq, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum TransitionBehaviour {
     Wait(usize), // transition to the next animation state after the current segment has reached a given frame (defaults to last frame).
+    WaitFraction(f32), // transition after the given fraction of the current segment has played (1.0 is the end of the last frame), regardless of its length.
+    WaitRange {
+        first: usize,
+        last: usize,
+    }, // transition after the current segment has reached any frame between `first` and `last` (inclusive).
     #[default]
     Immediate, // transition immediately to the next animation state without waiting for the current segment to finish.
 }
 
-#[derive(Default, Debug, Clone, Eq, PartialEq)]
+impl TransitionBehaviour {
+    /// the frames of a segment with `segment_length` frames that a transition may be taken after, or [`None`] for immediate transitions.
+    /// Frames past the end of the segment are taken to be the last frame.
+    pub fn exit_frames(&self, segment_length: usize) -> Option<RangeInclusive<usize>> {
+        let last_frame = segment_length.saturating_sub(1);
+        return match self {
+            TransitionBehaviour::Wait(frame) => {
+                Some((*frame).min(last_frame)..=(*frame).min(last_frame))
+            }
+            TransitionBehaviour::WaitFraction(fraction) => {
+                // the first frame that ends at or after the fraction of the segment
+                let frame = ((fraction * segment_length as f32).ceil() as usize)
+                    .saturating_sub(1)
+                    .min(last_frame);
+                Some(frame..=frame)
+            }
+            TransitionBehaviour::WaitRange { first, last } => {
+                Some((*first).min(last_frame)..=(*last).min(last_frame))
+            }
+            TransitionBehaviour::Immediate => None,
+        };
+    }
+}
+
+/// How many times a segment plays before a waiting transition out of it is taken, see [`TransitionData::loops`].
+/// The number of plays is chosen at random between `min` and `max` (inclusive) each time the transition is about to be taken.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoopCount {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl LoopCount {
+    /// the segment plays exactly `count` times.
+    pub fn exactly(count: usize) -> Self {
+        return LoopCount {
+            min: count,
+            max: count,
+        };
+    }
+
+    /// the segment plays a random number of times between `min` and `max` (inclusive).
+    pub fn between(min: usize, max: usize) -> Self {
+        return LoopCount { min: min, max: max };
+    }
+}
+
+impl Default for LoopCount {
+    fn default() -> Self {
+        return LoopCount::exactly(1);
+    }
+}
+
+impl From<usize> for LoopCount {
+    fn from(count: usize) -> Self {
+        return LoopCount::exactly(count);
+    }
+}
+
+impl From<RangeInclusive<usize>> for LoopCount {
+    fn from(range: RangeInclusive<usize>) -> Self {
+        return LoopCount::between(*range.start(), *range.end());
+    }
+}
+
+#[derive(Default, Debug, Clone, Eq, PartialEq, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum SegmentBehaviour {
     #[default]
     Forward,
     Backward,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct SegmentInterval {
     pub start: usize,
     pub length: usize,
@@ -38,24 +113,211 @@ impl SegmentInterval {
             length: length,
         };
     }
+
+    /// the first `length` frames of a row of a grid texture atlas with `columns` frames in each row.
+    pub fn row(row: usize, columns: usize, length: usize) -> Self {
+        return Self::new(row * columns, length);
+    }
+}
+
+/// consecutive frames of a grid texture atlas starting at a column and row, see [`SegmentFrames::Grid`].
+#[derive(Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SegmentGrid {
+    pub column: usize,
+    pub row: usize,
+    pub length: usize,
+}
+
+impl SegmentGrid {
+    pub fn new(column: usize, row: usize, length: usize) -> Self {
+        return Self {
+            column: column,
+            row: row,
+            length: length,
+        };
+    }
+
+    /// the interval of atlas indices of these frames, or [`None`] if the column or row is outside of the atlas.
+    fn resolve(&self, columns: usize, rows: usize) -> Option<SegmentInterval> {
+        if self.column >= columns || self.row >= rows {
+            return None;
+        }
+        return Some(SegmentInterval::new(
+            self.row * columns + self.column,
+            self.length,
+        ));
+    }
+}
+
+/// the number of columns and rows of a texture atlas created with [`TextureAtlas::from_grid`], the columns are counted along the first row.
+fn grid_size(atlas: &TextureAtlas) -> (usize, usize) {
+    let Some(first) = atlas.textures.first() else {
+        return (0, 0);
+    };
+    let columns = atlas
+        .textures
+        .iter()
+        .take_while(|rect| rect.min.y == first.min.y)
+        .count();
+    return (columns, atlas.len().div_ceil(columns));
+}
+
+/// the frames of a segment in the order that they are played. Frame indices used by transitions (see [`TransitionData`]) are positions in this sequence.
+#[derive(Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum SegmentFrames {
+    /// consecutive indices of the segment's texture atlas.
+    Interval(SegmentInterval),
+    /// indices of the segment's texture atlas, these may be in any order and may be repeated (e.g. `vec![0, 1, 2, 1, 0]`).
+    Indices(Vec<usize>),
+    /// consecutive indices of the segment's texture atlas, starting at a column and row of the atlas' grid.
+    /// The grid is only known once the atlas has loaded, until then the segment has no frames to show (its length is known).
+    /// See [`SpriteAnimationGraph::resolve_segments`].
+    Grid(SegmentGrid),
+    /// every index of the segment's texture atlas from `start` to the end of the atlas. The length is inferred once the atlas has loaded,
+    /// until then the segment has no frames (see [`SpriteAnimationGraph::resolve_segments`]).
+    Remaining { start: usize },
+    /// each frame is an index of its own texture atlas, the segment's texture atlas is not used.
+    /// These frames cannot be serialized.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    Atlases(Vec<(Handle<TextureAtlas>, usize)>),
+    /// each frame is an individual image, the segment's texture atlas is not used.
+    /// The animated entity must have a [`Handle<Image>`] (e.g. a [`SpriteBundle`]) rather than a texture atlas. These frames cannot be serialized.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    Images(Vec<Handle<Image>>),
+}
+
+/// a single frame of a segment, resolved to the texture that it is drawn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frame<'a> {
+    /// an index of a texture atlas.
+    Atlas(&'a Handle<TextureAtlas>, usize),
+    /// an individual image.
+    Image(&'a Handle<Image>),
+}
+
+impl SegmentFrames {
+    /// the number of frames in the segment, this is 0 for [`SegmentFrames::Remaining`] until the length has been inferred.
+    pub fn len(&self) -> usize {
+        return match self {
+            SegmentFrames::Interval(interval) => interval.length,
+            SegmentFrames::Indices(indices) => indices.len(),
+            SegmentFrames::Grid(grid) => grid.length,
+            SegmentFrames::Remaining { .. } => 0,
+            SegmentFrames::Atlases(frames) => frames.len(),
+            SegmentFrames::Images(images) => images.len(),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    /// whether the number of frames is known before the texture atlas has loaded.
+    pub(crate) fn is_sized(&self) -> bool {
+        return !matches!(self, SegmentFrames::Remaining { .. });
+    }
+
+    /// the largest index of each texture atlas used by these frames.
+    fn last_indices<'a>(
+        &'a self,
+        texture_atlas_handle: &'a Handle<TextureAtlas>,
+    ) -> Vec<(&'a Handle<TextureAtlas>, usize)> {
+        return match self {
+            SegmentFrames::Interval(interval) if interval.length > 0 => {
+                vec![(texture_atlas_handle, interval.start + interval.length - 1)]
+            }
+            SegmentFrames::Indices(indices) => indices
+                .iter()
+                .max()
+                .map(|index| (texture_atlas_handle, *index))
+                .into_iter()
+                .collect(),
+            SegmentFrames::Atlases(frames) => frames
+                .iter()
+                .map(|(handle, index)| (handle, *index))
+                .collect(),
+            _ => Vec::new(),
+        };
+    }
+}
+
+impl From<SegmentInterval> for SegmentFrames {
+    fn from(interval: SegmentInterval) -> Self {
+        return SegmentFrames::Interval(interval);
+    }
+}
+
+impl From<SegmentGrid> for SegmentFrames {
+    fn from(grid: SegmentGrid) -> Self {
+        return SegmentFrames::Grid(grid);
+    }
+}
+
+impl From<Vec<usize>> for SegmentFrames {
+    fn from(indices: Vec<usize>) -> Self {
+        return SegmentFrames::Indices(indices);
+    }
 }
 
 /// a segment is a single "state" in the animation graph.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct SegmentData {
     pub segment_behaviour: SegmentBehaviour,
     // sprite scaling to apply to this segment. This can be used to "flip" a sprite if negative values are provided.
     // TODO pub scale: Vec2,
-    pub segment_interval: SegmentInterval,
+    pub segment_frames: SegmentFrames,
+    /// the texture atlas of [`SegmentFrames::Interval`] and [`SegmentFrames::Indices`] frames.
+    /// handles cannot be serialized, the atlas will need to be set again after deserializing.
+    #[cfg_attr(feature = "serialize", serde(skip))]
     pub texture_atlas_handle: Handle<TextureAtlas>,
 }
 
-#[derive(Debug, Clone)]
+impl SegmentData {
+    /// the texture of the given `frame` of the segment, or [`None`] if the frames have not been resolved yet (see [`SegmentFrames::Grid`]).
+    pub fn frame(&self, frame: usize) -> Option<Frame<'_>> {
+        return match &self.segment_frames {
+            SegmentFrames::Interval(interval) => Some(Frame::Atlas(
+                &self.texture_atlas_handle,
+                interval.start + frame,
+            )),
+            SegmentFrames::Indices(indices) => {
+                Some(Frame::Atlas(&self.texture_atlas_handle, indices[frame]))
+            }
+            SegmentFrames::Grid(_) | SegmentFrames::Remaining { .. } => None,
+            SegmentFrames::Atlases(frames) => Some(Frame::Atlas(&frames[frame].0, frames[frame].1)),
+            SegmentFrames::Images(images) => Some(Frame::Image(&images[frame])),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransitionData {
     /// transition behaviour
     pub transition_behaviour: TransitionBehaviour,
     /// the starting frame index of the next segment
     pub transition_to_frame: usize,
+    /// when several equally short paths to the desired state exist, the priorities of their transitions are compared in the order the
+    /// transitions are taken, and the path with the higher priority at the first transition that differs is chosen.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub priority: i32,
+    /// if set, the next segment continues at the same fraction of its length that the current segment has played (plus this offset, wrapping around),
+    /// rather than at [`transition_to_frame`](Self::transition_to_frame). This keeps cycles such as walking and running in phase.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub phase_offset: Option<f32>,
+    /// the number of times the current segment plays (counting from when it was entered) before a waiting transition is taken, e.g. to blink
+    /// three times before going back to idle. Until then the segment starts over whenever it reaches the frame the transition waits for.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub loops: LoopCount,
+    /// the chance of taking this transition when its state loops while it is the desired state, relative to the weights of the state's other
+    /// transitions. A state with weighted transitions plays one of them at random each time it loops (its self-transition's weight is the chance
+    /// of just looping), then returns to the state, e.g. to play idle fidgets. The [`loops`](Self::loops) of the self-transition are the minimum
+    /// number of times the state plays between variants. Transitions with a weight of 0 (the default) are never chosen at random.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub weight: u32,
 }
 
 impl TransitionData {
@@ -80,6 +342,27 @@ impl TransitionData {
         Self {
             transition_behaviour: TransitionBehaviour::Wait(wait_frame),
             transition_to_frame: to_frame,
+            ..Default::default()
+        }
+    }
+
+    /// Transitions will wait for the given fraction of the current segment to play (e.g. 0.5 for half of it) before continuing to the start of the next segment.
+    /// Unlike a frame, the fraction does not change when the number of frames in the segment does.
+    pub fn wait_for_fraction(fraction: f32) -> Self {
+        Self {
+            transition_behaviour: TransitionBehaviour::WaitFraction(fraction),
+            ..Default::default()
+        }
+    }
+
+    /// Transitions will wait for the current segment to reach any of the given [`frames`] (e.g. `2..=4`) before continuing to the start of the next segment.
+    pub fn wait_for_range(frames: RangeInclusive<usize>) -> Self {
+        Self {
+            transition_behaviour: TransitionBehaviour::WaitRange {
+                first: *frames.start(),
+                last: *frames.end(),
+            },
+            ..Default::default()
         }
     }
 
@@ -93,8 +376,56 @@ impl TransitionData {
         Self {
             transition_behaviour: TransitionBehaviour::Immediate,
             transition_to_frame: frame,
+            ..Default::default()
         }
     }
+
+    /// Continues to the given [`frame`] of the next segment rather than its start.
+    pub fn then_to(mut self, frame: usize) -> Self {
+        self.transition_to_frame = frame;
+        return self;
+    }
+
+    /// Continues the next segment in phase with the current segment, offset by the fraction `offset` of the next segment (see [`phase_offset`](Self::phase_offset)).
+    pub fn sync_phase(mut self, offset: f32) -> Self {
+        self.phase_offset = Some(offset);
+        return self;
+    }
+
+    /// the frame of a segment with `segment_length` frames that the transition continues at, given the fraction of the current segment that has played.
+    pub fn to_frame(&self, progress: f32, segment_length: usize) -> usize {
+        return match self.phase_offset {
+            Some(offset) => {
+                let phase = (progress + offset).rem_euclid(1.);
+                ((phase * segment_length as f32) as usize).min(segment_length.saturating_sub(1))
+            }
+            None => self.transition_to_frame,
+        };
+    }
+
+    /// Plays the current segment the given number of times (e.g. `3` or `2..=4`) before the transition is taken, see [`loops`](Self::loops).
+    pub fn with_loops(mut self, loops: impl Into<LoopCount>) -> Self {
+        self.loops = loops.into();
+        return self;
+    }
+
+    /// Sets the [`weight`](Self::weight) of choosing this transition at random each time its state loops.
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        return self;
+    }
+
+    /// Sets the [`priority`](Self::priority) used to break ties between equally short paths (the default is 0).
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        return self;
+    }
+}
+
+impl Priority for TransitionData {
+    fn priority(&self) -> i32 {
+        return self.priority;
+    }
 }
 
 impl Default for TransitionData {
@@ -102,14 +433,166 @@ impl Default for TransitionData {
         Self {
             transition_behaviour: TransitionBehaviour::default(),
             transition_to_frame: 0,
+            priority: 0,
+            phase_offset: None,
+            loops: LoopCount::default(),
+            weight: 0,
         }
     }
 }
 
+/// Problems found while constructing an animation graph, see [`crate::SpriteAnimationGraphBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationGraphError<S: SpriteAnimationState> {
+    /// the state was added to the graph more than once.
+    DuplicateState(S),
+    /// a transition refers to a state that was not added to the graph.
+    UnknownState(S),
+    /// the transition between the two states was added more than once.
+    DuplicateTransition(S, S),
+    /// [`TransitionBehaviour::Immediate`] was used in a self-transition of the state.
+    ImmediateSelfTransition(S),
+    /// the [`TransitionData::transition_to_frame`] of a transition is outside of the segment it transitions to.
+    FrameOutOfRange {
+        from: S,
+        to: S,
+        frame: usize,
+        length: usize,
+    },
+    /// the [`TransitionData::loops`] of a transition are empty (or zero), or were given to an immediate transition.
+    InvalidLoops { from: S, to: S, loops: LoopCount },
+    /// the [`TransitionBehaviour::WaitFraction`] of a transition is not between 0 and 1, or its [`TransitionBehaviour::WaitRange`] is empty.
+    InvalidExitTime { from: S, to: S },
+    /// a frame of the segment is outside of its texture atlas.
+    FrameOutsideAtlas {
+        state: S,
+        index: usize,
+        atlas_length: usize,
+    },
+    /// the column or row of a [`SegmentFrames::Grid`] segment is outside of the grid of its texture atlas.
+    GridOutOfRange {
+        state: S,
+        column: usize,
+        row: usize,
+        columns: usize,
+        rows: usize,
+    },
+}
+
+impl<S: SpriteAnimationState> std::fmt::Display for AnimationGraphError<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            AnimationGraphError::DuplicateState(state) => write!(
+                f,
+                "Animation state {:?} was added to the animation graph more than once.",
+                state
+            ),
+            AnimationGraphError::UnknownState(state) => write!(
+                f,
+                "Animation state {:?} was not part of the animation graph, did you add it with [`state`]?",
+                state
+            ),
+            AnimationGraphError::DuplicateTransition(from, to) => write!(
+                f,
+                "Transition {:?} was added to the animation graph more than once.",
+                (from, to)
+            ),
+            AnimationGraphError::ImmediateSelfTransition(state) => write!(
+                f,
+                "{:?} cannot be used in a self-transition {:?}.",
+                TransitionBehaviour::Immediate,
+                (state, state)
+            ),
+            AnimationGraphError::FrameOutOfRange {
+                from,
+                to,
+                frame,
+                length,
+            } => write!(
+                f,
+                "Invalid [`transition_to_frame`] {:?} for edge {:?}, {:?} segment is not long enough ({:?}).",
+                frame,
+                (from, to),
+                to,
+                length
+            ),
+            AnimationGraphError::InvalidLoops { from, to, loops } => write!(
+                f,
+                "Invalid [`loops`] {:?} for edge {:?}, a segment must play at least once and loops can only be used by waiting transitions.",
+                loops,
+                (from, to)
+            ),
+            AnimationGraphError::InvalidExitTime { from, to } => write!(
+                f,
+                "Invalid exit time for edge {:?}, the fraction waited for must be between 0 and 1 and the range of frames waited for cannot be empty.",
+                (from, to)
+            ),
+            AnimationGraphError::FrameOutsideAtlas {
+                state,
+                index,
+                atlas_length,
+            } => write!(
+                f,
+                "Invalid frame {:?} for segment {:?}, its texture atlas only has {:?} frames.",
+                index, state, atlas_length
+            ),
+            AnimationGraphError::GridOutOfRange {
+                state,
+                column,
+                row,
+                columns,
+                rows,
+            } => write!(
+                f,
+                "Invalid grid position {:?} for segment {:?}, its texture atlas has {:?} columns and {:?} rows.",
+                (column, row),
+                state,
+                columns,
+                rows
+            ),
+        };
+    }
+}
+
+impl<S: SpriteAnimationState> std::error::Error for AnimationGraphError<S> {}
+
+/// What an animation does when its desired state cannot be reached from its current state, see [`SpriteAnimationGraph::set_unreachable_behaviour`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnreachableBehaviour<S: SpriteAnimationState> {
+    /// panic, this is the default.
+    Panic,
+    /// stay in the current state until a reachable state is desired.
+    Stay,
+    /// jump directly to the start of the desired state.
+    Snap,
+    /// go to the given state instead, jumping directly to it if it cannot be reached either.
+    Fallback(S),
+}
+
+impl<S: SpriteAnimationState> Default for UnreachableBehaviour<S> {
+    fn default() -> Self {
+        return UnreachableBehaviour::Panic;
+    }
+}
+
+/// Sent when an animation's desired state cannot be reached from its current state, unless the [`UnreachableBehaviour`] is to panic.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct AnimationPathError<S: SpriteAnimationState> {
+    pub entity: Entity,
+    /// the state of the animation when the desired state was found to be unreachable.
+    pub current: S,
+    pub desired: S,
+}
+
 #[derive(Resource, Debug)]
 pub struct SpriteAnimationGraph<S: SpriteAnimationState> {
     animation_graph: Graph<SegmentData, TransitionData>,
     animation_states: IndexMap<S, Vertex>,
+    // every change to the graph that requires animations to be updated, the number of changes is the revision of the graph.
+    // removing a state relabels vertices, so the removed vertex is recorded to allow animations to catch up.
+    // resolving segments changes the frames that animations show without relabelling vertices.
+    changes: Vec<Option<Vertex>>,
+    unreachable_behaviour: UnreachableBehaviour<S>,
     // this is used to cache shortest path computations
     // _desired_vertex_cache: AtomicUsize,
     // _next_vertex_cache: AtomicUsize,
@@ -119,6 +602,8 @@ impl<S: SpriteAnimationState> SpriteAnimationGraph<S> {
         return SpriteAnimationGraph {
             animation_graph: Graph::new(),
             animation_states: IndexMap::new(),
+            changes: Vec::new(),
+            unreachable_behaviour: UnreachableBehaviour::default(),
         };
     }
 
@@ -130,31 +615,32 @@ impl<S: SpriteAnimationState> SpriteAnimationGraph<S> {
     }
 
     pub fn add_transition(&mut self, edge: Edge, transition_data: TransitionData) -> Edge {
+        self.add_transitions([(edge, transition_data)]);
+        return edge;
+    }
+
+    /// Adds several transitions at once, which is faster than adding them one at a time with [`add_transition`](Self::add_transition)
+    /// as the graph only reorganises its transitions once.
+    pub fn add_transitions(
+        &mut self,
+        transitions: impl IntoIterator<Item = (Edge, TransitionData)>,
+    ) {
+        let transitions: Vec<(Edge, TransitionData)> = transitions.into_iter().collect();
+        for (edge, transition_data) in &transitions {
+            self.check_new_transition(*edge, transition_data);
+        }
+        // everything checked out fine, add the edges.
+        self.animation_graph.add_edges(transitions);
+    }
+
+    /// panics if the transition cannot be added to the graph.
+    fn check_new_transition(&self, edge: Edge, transition_data: &TransitionData) {
         if self.animation_graph.contains_vertex(edge.0)
             && self.animation_graph.contains_vertex(edge.1)
         {
-            if edge.0 == edge.1
-                && transition_data.transition_behaviour == TransitionBehaviour::Immediate
-            {
-                panic!(
-                    "{:?} cannot be used in a self-transition {:?}.",
-                    TransitionBehaviour::Immediate,
-                    edge
-                )
+            if let Err(error) = self.check_transition(edge, transition_data) {
+                panic!("{}", error);
             }
-            // check that the transition_to_index is value
-            let vertex1_segment_length = self
-                .animation_graph
-                .get_vertex_metadata(edge.1)
-                .unwrap()
-                .segment_interval
-                .length;
-            if transition_data.transition_to_frame >= vertex1_segment_length {
-                panic!("Invalid [`transition_to_index`] for edge {:?}, {:?} segment is not long enough ({:?}).", (self.get_state(edge.0), self.get_state(edge.1)),  self.get_state(edge.1), vertex1_segment_length );
-            }
-            // everything checked out fine, add the edge.
-            self.animation_graph.add_edge(edge, transition_data);
-            return edge;
         } else {
             let no_vertex = if self.animation_graph.contains_vertex(edge.0) {
                 edge.1
@@ -165,6 +651,58 @@ impl<S: SpriteAnimationState> SpriteAnimationGraph<S> {
         }
     }
 
+    /// Checks whether `transition_data` can be used for the transition between two existing vertices.
+    pub(crate) fn check_transition(
+        &self,
+        edge: Edge,
+        transition_data: &TransitionData,
+    ) -> Result<(), AnimationGraphError<S>> {
+        if edge.0 == edge.1
+            && transition_data.transition_behaviour == TransitionBehaviour::Immediate
+        {
+            return Err(AnimationGraphError::ImmediateSelfTransition(
+                *self.get_state(edge.0),
+            ));
+        }
+        let valid_exit_time = match transition_data.transition_behaviour {
+            TransitionBehaviour::WaitFraction(fraction) => (0.0..=1.0).contains(&fraction),
+            TransitionBehaviour::WaitRange { first, last } => first <= last,
+            _ => true,
+        };
+        if !valid_exit_time {
+            return Err(AnimationGraphError::InvalidExitTime {
+                from: *self.get_state(edge.0),
+                to: *self.get_state(edge.1),
+            });
+        }
+        let loops = transition_data.loops;
+        if loops.min == 0
+            || loops.min > loops.max
+            || (transition_data.transition_behaviour == TransitionBehaviour::Immediate
+                && loops != LoopCount::default())
+        {
+            return Err(AnimationGraphError::InvalidLoops {
+                from: *self.get_state(edge.0),
+                to: *self.get_state(edge.1),
+                loops: loops,
+            });
+        }
+        // check that the transition_to_index is value, this is checked once the texture atlas has loaded if the length of the segment is not known yet.
+        let vertex1_segment_frames = &self.get_segment_data(edge.1).segment_frames;
+        let vertex1_segment_length = vertex1_segment_frames.len();
+        if vertex1_segment_frames.is_sized()
+            && transition_data.transition_to_frame >= vertex1_segment_length
+        {
+            return Err(AnimationGraphError::FrameOutOfRange {
+                from: *self.get_state(edge.0),
+                to: *self.get_state(edge.1),
+                frame: transition_data.transition_to_frame,
+                length: vertex1_segment_length,
+            });
+        }
+        return Ok(());
+    }
+
     pub fn get_vertex(&self, state: &S) -> Vertex {
         if let Some(result) = self.animation_states.get(state) {
             return *result;
@@ -205,6 +743,204 @@ impl<S: SpriteAnimationState> SpriteAnimationGraph<S> {
         };
     }
 
+    /// Resolves and validates the frames of every segment whose texture atlas has loaded: [`SegmentFrames::Grid`] segments are resolved
+    /// into a [`SegmentFrames::Interval`] and the length of [`SegmentFrames::Remaining`] segments is inferred from the atlas.
+    /// Returns every segment with frames outside of its atlas and every transition to a frame outside of an inferred segment,
+    /// the latter are clamped to the last frame of the segment.
+    /// Segments whose atlas has not loaded yet are left unresolved. This is done by the [`crate::SpriteAnimationPlugin`] when atlases are loaded.
+    pub fn resolve_segments(
+        &mut self,
+        atlases: &Assets<TextureAtlas>,
+    ) -> Vec<AnimationGraphError<S>> {
+        let mut errors = Vec::new();
+        let mut resolved = false;
+        let mut inferred = Vec::new();
+        for (state, vertex) in &self.animation_states {
+            let segment_data = self
+                .animation_graph
+                .get_vertex_metadata_mut(*vertex)
+                .unwrap();
+            if let Some(atlas) = atlases.get(&segment_data.texture_atlas_handle) {
+                match &segment_data.segment_frames {
+                    SegmentFrames::Grid(grid) => {
+                        let (columns, rows) = grid_size(atlas);
+                        match grid.resolve(columns, rows) {
+                            Some(interval) => {
+                                segment_data.segment_frames = SegmentFrames::Interval(interval);
+                                resolved = true;
+                            }
+                            None => errors.push(AnimationGraphError::GridOutOfRange {
+                                state: *state,
+                                column: grid.column,
+                                row: grid.row,
+                                columns: columns,
+                                rows: rows,
+                            }),
+                        }
+                    }
+                    SegmentFrames::Remaining { start } => {
+                        if *start < atlas.len() {
+                            segment_data.segment_frames = SegmentFrames::Interval(
+                                SegmentInterval::new(*start, atlas.len() - start),
+                            );
+                            inferred.push(*vertex);
+                            resolved = true;
+                        } else {
+                            errors.push(AnimationGraphError::FrameOutsideAtlas {
+                                state: *state,
+                                index: *start,
+                                atlas_length: atlas.len(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            for (handle, index) in segment_data
+                .segment_frames
+                .last_indices(&segment_data.texture_atlas_handle)
+            {
+                if let Some(atlas) = atlases.get(handle) {
+                    if index >= atlas.len() {
+                        errors.push(AnimationGraphError::FrameOutsideAtlas {
+                            state: *state,
+                            index: index,
+                            atlas_length: atlas.len(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // transitions to segments whose length was not known could not be checked when they were added.
+        // transitions to a frame past the end of the inferred segment go to its last frame instead.
+        let mut clamped = Vec::new();
+        for (edge, transition_data) in self.animation_graph.edges() {
+            if inferred.contains(&edge.1) {
+                if let Err(error) = self.check_transition(edge, transition_data) {
+                    if let AnimationGraphError::FrameOutOfRange { length, .. } = error {
+                        clamped.push((edge, length - 1));
+                    }
+                    errors.push(error);
+                }
+            }
+        }
+        for (edge, frame) in clamped {
+            self.animation_graph
+                .get_edge_metadata_mut(edge)
+                .unwrap()
+                .transition_to_frame = frame;
+        }
+        // animations in resolved segments have new frames to show.
+        if resolved {
+            self.changes.push(None);
+        }
+        return errors;
+    }
+
+    /// Removes an animation state and all of its transitions, returning the segment data of the state.
+    /// Vertices of states that were added after the removed state will each be shifted down by one.
+    /// Animations that are in (or on their way through) the removed state will be re-planned the next time they are updated.
+    pub fn remove_state(&mut self, state: &S) -> Option<SegmentData> {
+        let vertex = self.animation_states.shift_remove(state)?;
+        for other in self.animation_states.values_mut() {
+            if *other > vertex {
+                *other -= 1;
+            }
+        }
+        self.changes.push(Some(vertex));
+        return self.animation_graph.remove_node(vertex);
+    }
+
+    /// Removes a transition, returning its transition data.
+    /// Animations whose path uses the removed transition will be re-planned the next time they are updated.
+    pub fn remove_transition(&mut self, edge: Edge) -> Option<TransitionData> {
+        let transition_data = self.animation_graph.remove_edge(edge)?;
+        self.changes.push(None);
+        return Some(transition_data);
+    }
+
+    /// Replaces the data of an existing transition, returning the previous transition data.
+    pub fn replace_transition(
+        &mut self,
+        edge: Edge,
+        transition_data: TransitionData,
+    ) -> TransitionData {
+        let previous = self.get_transition_data(edge).clone();
+        if let Err(error) = self.check_transition(edge, &transition_data) {
+            panic!("{}", error);
+        }
+        self.animation_graph.add_edge(edge, transition_data);
+        return previous;
+    }
+
+    /// the number of changes made to the graph that animations need to catch up with.
+    pub(crate) fn revision(&self) -> usize {
+        return self.changes.len();
+    }
+
+    /// Relabels a vertex from a previous `revision` of the graph, returns [`None`] if the vertex has since been removed.
+    /// Vertices from a revision newer than the graph (e.g. of an animation that was saved with another graph) are always stale.
+    pub(crate) fn update_vertex(&self, vertex: Vertex, revision: usize) -> Option<Vertex> {
+        return self.changes.get(revision..)?.iter().flatten().try_fold(
+            vertex,
+            |vertex, removed| {
+                if vertex == *removed {
+                    None
+                } else if vertex > *removed {
+                    Some(vertex - 1)
+                } else {
+                    Some(vertex)
+                }
+            },
+        );
+    }
+
+    /// Checks whether every transition on a path (in reverse order, see [`crate::SpriteAnimation`]) starting at `current` exists.
+    pub(crate) fn is_valid_path(&self, current: Vertex, path: &[Vertex]) -> bool {
+        let mut from = current;
+        for to in path.iter().rev() {
+            if !self.animation_graph.contains_edge((from, *to)) {
+                return false;
+            }
+            from = *to;
+        }
+        return true;
+    }
+
+    /// Sets what animations do when their desired state cannot be reached from their current state.
+    pub fn set_unreachable_behaviour(&mut self, unreachable_behaviour: UnreachableBehaviour<S>) {
+        if let UnreachableBehaviour::Fallback(state) = &unreachable_behaviour {
+            // check that the fallback state exists
+            self.get_vertex(state);
+        }
+        self.unreachable_behaviour = unreachable_behaviour;
+    }
+
+    pub fn unreachable_behaviour(&self) -> &UnreachableBehaviour<S> {
+        return &self.unreachable_behaviour;
+    }
+
+    /// the transitions of `vertex` that may be chosen at random when it loops, with their weights (see [`TransitionData::weight`]).
+    pub(crate) fn variants(&self, vertex: Vertex) -> impl Iterator<Item = (Vertex, u32)> + '_ {
+        return self
+            .animation_graph
+            .outgoing_edges(vertex)
+            .filter(|(_, transition_data)| transition_data.weight > 0)
+            .map(|(vertex, transition_data)| (vertex, transition_data.weight));
+    }
+
+    /// the vertex of `state`, or [`None`] if it is not part of the animation graph.
+    pub(crate) fn find_vertex(&self, state: &S) -> Option<Vertex> {
+        return self.animation_states.get(state).copied();
+    }
+
+    /// the shortest path between two vertices (see [`shortest_path`](Self::shortest_path)), or [`None`] if there is no such path.
+    pub fn find_path(&self, current: Vertex, desired: Vertex) -> Option<Vec<Vertex>> {
+        return self.animation_graph.shortest_path(current, desired).ok();
+    }
+
     pub fn shortest_path(&self, current: Vertex, desired: Vertex) -> Vec<Vertex> {
         return match self.animation_graph.shortest_path(current, desired) {
             Ok(path) => path,
@@ -302,3 +1038,149 @@ impl<S: SpriteAnimationState> SpriteAnimationGraph<S> {
     //     }
     //}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{segment_data, State};
+    use crate::SpriteAnimationGraphBuilder;
+
+    fn animation_graph() -> SpriteAnimationGraph<State> {
+        return SpriteAnimationGraphBuilder::new()
+            .state(State::A, segment_data())
+            .state(State::B, segment_data())
+            .state(State::C, segment_data())
+            .transition(State::A, State::B, TransitionData::wait())
+            .transition(State::B, State::C, TransitionData::wait())
+            .transition(State::C, State::A, TransitionData::wait())
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn invalid_exit_times_are_rejected() {
+        let errors = SpriteAnimationGraphBuilder::new()
+            .state(State::A, segment_data())
+            .state(State::B, segment_data())
+            .state(State::C, segment_data())
+            .state(State::D, segment_data())
+            .transition(State::A, State::B, TransitionData::wait_for_range(3..=1))
+            .transition(State::B, State::C, TransitionData::wait_for_fraction(1.5))
+            .transition(
+                State::C,
+                State::D,
+                TransitionData::wait_for_fraction(f32::NAN),
+            )
+            .transition(State::D, State::A, TransitionData::wait_for_fraction(1.))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                AnimationGraphError::InvalidExitTime {
+                    from: State::A,
+                    to: State::B
+                },
+                AnimationGraphError::InvalidExitTime {
+                    from: State::B,
+                    to: State::C
+                },
+                AnimationGraphError::InvalidExitTime {
+                    from: State::C,
+                    to: State::D
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn remove_state_relabels_later_vertices() {
+        let mut animation_graph = animation_graph();
+        assert!(animation_graph.remove_state(&State::A).is_some());
+        assert_eq!(animation_graph.find_vertex(&State::A), None);
+        assert_eq!(animation_graph.get_vertex(&State::B), 0);
+        assert_eq!(animation_graph.get_vertex(&State::C), 1);
+        assert!(animation_graph.is_valid_path(0, &[1]));
+        // the transitions of the removed state are gone
+        assert!(animation_graph.find_path(1, 0).is_none());
+        assert!(animation_graph.remove_state(&State::A).is_none());
+    }
+
+    #[test]
+    fn update_vertex_follows_removed_states() {
+        let mut animation_graph = animation_graph();
+        animation_graph.remove_state(&State::A);
+        assert_eq!(animation_graph.revision(), 1);
+        assert_eq!(animation_graph.update_vertex(0, 0), None);
+        assert_eq!(animation_graph.update_vertex(1, 0), Some(0));
+        assert_eq!(animation_graph.update_vertex(2, 0), Some(1));
+        // vertices of the current revision are already up to date
+        assert_eq!(animation_graph.update_vertex(0, 1), Some(0));
+        assert_eq!(animation_graph.update_vertex(1, 1), Some(1));
+    }
+
+    #[test]
+    fn update_vertex_from_a_newer_revision_is_stale() {
+        let animation_graph = animation_graph();
+        assert_eq!(animation_graph.revision(), 0);
+        assert_eq!(animation_graph.update_vertex(0, 1), None);
+    }
+
+    #[test]
+    fn remove_transition_invalidates_paths() {
+        let mut animation_graph = animation_graph();
+        let (a, b, c) = (0, 1, 2);
+        assert_eq!(animation_graph.find_path(a, c), Some(vec![c, b, a]));
+        assert!(animation_graph.is_valid_path(a, &[c, b]));
+
+        assert!(animation_graph.remove_transition((b, c)).is_some());
+        assert_eq!(animation_graph.revision(), 1);
+        assert!(!animation_graph.is_valid_path(a, &[c, b]));
+        assert_eq!(animation_graph.find_path(a, c), None);
+        assert!(animation_graph.remove_transition((b, c)).is_none());
+    }
+
+    #[test]
+    fn resolve_segments_clamps_transitions_into_inferred_segments() {
+        let mut app = App::new();
+        app.add_plugins(AssetPlugin::default())
+            .add_asset::<TextureAtlas>();
+        let mut atlases = app.world.resource_mut::<Assets<TextureAtlas>>();
+        let mut atlas = TextureAtlas::new_empty(Handle::default(), Vec2::new(5., 1.));
+        for x in 0..5 {
+            atlas.add_texture(Rect::new(x as f32, 0., x as f32 + 1., 1.));
+        }
+        let handle = atlases.add(atlas);
+
+        let mut animation_graph = SpriteAnimationGraphBuilder::new()
+            .state(State::A, segment_data())
+            .state(
+                State::B,
+                SegmentData {
+                    segment_frames: SegmentFrames::Remaining { start: 2 },
+                    segment_behaviour: SegmentBehaviour::Forward,
+                    texture_atlas_handle: handle,
+                },
+            )
+            .transition(State::A, State::B, TransitionData::immediate_to(4))
+            .transition(State::B, State::A, TransitionData::wait())
+            .build()
+            .unwrap();
+        let errors = animation_graph.resolve_segments(&atlases);
+        assert!(matches!(
+            errors[..],
+            [AnimationGraphError::FrameOutOfRange {
+                frame: 4,
+                length: 3,
+                ..
+            }]
+        ));
+        assert_eq!(
+            animation_graph
+                .get_transition_data((0, 1))
+                .transition_to_frame,
+            2
+        );
+    }
+}
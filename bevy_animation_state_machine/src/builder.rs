@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use crate::animation_graph::{
+    AnimationGraphError, SegmentData, SpriteAnimationGraph, TransitionData, UnreachableBehaviour,
+};
+use crate::SpriteAnimationState;
+
+/// Constructs a [`SpriteAnimationGraph`] from animation states rather than vertices.
+///
+/// ```ignore
+/// let animation_graph = SpriteAnimationGraphBuilder::new()
+///     .state(PlayerState::Idle, idle_data)
+///     .state(PlayerState::Running, running_data)
+///     .transition(PlayerState::Idle, PlayerState::Idle, TransitionData::wait())
+///     .transition(PlayerState::Idle, PlayerState::Running, TransitionData::immediate_to(3))
+///     .build()?;
+/// ```
+///
+/// Nothing is checked until [`build`](Self::build), which reports every problem with the graph at once.
+#[derive(Debug, Clone)]
+pub struct SpriteAnimationGraphBuilder<S: SpriteAnimationState> {
+    states: Vec<(S, SegmentData)>,
+    transitions: Vec<(S, S, TransitionData)>,
+    unreachable_behaviour: UnreachableBehaviour<S>,
+}
+
+impl<S: SpriteAnimationState> SpriteAnimationGraphBuilder<S> {
+    pub fn new() -> Self {
+        return Self {
+            states: Vec::new(),
+            transitions: Vec::new(),
+            unreachable_behaviour: UnreachableBehaviour::default(),
+        };
+    }
+
+    /// Adds an animation state that plays the segment described by `segment_data`.
+    pub fn state(mut self, state: S, segment_data: SegmentData) -> Self {
+        self.states.push((state, segment_data));
+        return self;
+    }
+
+    /// Adds a transition between two animation states, the states may be added before or after the transition.
+    pub fn transition(mut self, from: S, to: S, transition_data: TransitionData) -> Self {
+        self.transitions.push((from, to, transition_data));
+        return self;
+    }
+
+    /// Sets what animations do when their desired state cannot be reached, see [`SpriteAnimationGraph::set_unreachable_behaviour`].
+    pub fn unreachable_behaviour(mut self, unreachable_behaviour: UnreachableBehaviour<S>) -> Self {
+        self.unreachable_behaviour = unreachable_behaviour;
+        return self;
+    }
+
+    /// Creates the animation graph, or returns all of the problems found with the states and transitions.
+    pub fn build(self) -> Result<SpriteAnimationGraph<S>, Vec<AnimationGraphError<S>>> {
+        let mut errors = Vec::new();
+        let mut animation_graph = SpriteAnimationGraph::new();
+
+        let mut states = HashSet::new();
+        for (state, segment_data) in self.states {
+            if states.insert(state) {
+                animation_graph.add_state(state, segment_data);
+            } else {
+                errors.push(AnimationGraphError::DuplicateState(state));
+            }
+        }
+
+        let mut edges = HashSet::new();
+        let mut transitions = Vec::new();
+        for (from, to, transition_data) in self.transitions {
+            let mut known = true;
+            for state in [from, to] {
+                if !states.contains(&state) {
+                    errors.push(AnimationGraphError::UnknownState(state));
+                    known = false;
+                }
+            }
+            if !known {
+                continue;
+            }
+            if !edges.insert((from, to)) {
+                errors.push(AnimationGraphError::DuplicateTransition(from, to));
+                continue;
+            }
+            let edge = (
+                animation_graph.get_vertex(&from),
+                animation_graph.get_vertex(&to),
+            );
+            match animation_graph.check_transition(edge, &transition_data) {
+                Ok(()) => transitions.push((edge, transition_data)),
+                Err(error) => errors.push(error),
+            }
+        }
+        animation_graph.add_transitions(transitions);
+
+        match self.unreachable_behaviour {
+            UnreachableBehaviour::Fallback(state) if !states.contains(&state) => {
+                errors.push(AnimationGraphError::UnknownState(state));
+            }
+            unreachable_behaviour => {
+                animation_graph.set_unreachable_behaviour(unreachable_behaviour);
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(animation_graph);
+        }
+        return Err(errors);
+    }
+}
+
+impl<S: SpriteAnimationState> Default for SpriteAnimationGraphBuilder<S> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
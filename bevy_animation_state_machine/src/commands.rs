@@ -0,0 +1,47 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::animation_graph::SpriteAnimationGraph;
+use crate::component::SpriteAnimation;
+use crate::target::{AnimationTarget, SpriteTarget};
+use crate::SpriteAnimationState;
+
+/// Animation commands for an entity with a [`SpriteAnimation`].
+pub trait SpriteAnimationCommandsExt {
+    /// Places the entity's animation at `frame` of `state` immediately without following any transitions (e.g. for respawns, cutscenes
+    /// or network corrections), see [`SpriteAnimation::teleport`]. `state` also becomes the desired state of the entity, and its sprite or
+    /// UI image shows the new frame straight away.
+    fn teleport_animation<S: SpriteAnimationState>(&mut self, state: S, frame: usize) -> &mut Self;
+}
+
+impl<'w, 's, 'a> SpriteAnimationCommandsExt for EntityCommands<'w, 's, 'a> {
+    fn teleport_animation<S: SpriteAnimationState>(&mut self, state: S, frame: usize) -> &mut Self {
+        return self.add(move |entity: Entity, world: &mut World| {
+            world.resource_scope(|world, animation_graph: Mut<SpriteAnimationGraph<S>>| {
+                let Some(mut entity_mut) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                entity_mut.insert(state);
+                let Some(mut animation) = entity_mut.get_mut::<SpriteAnimation>() else {
+                    panic!(
+                        "Cannot teleport the animation of {:?}, it has no SpriteAnimation.",
+                        entity
+                    );
+                };
+                animation.teleport(&animation_graph, &state, frame);
+
+                let segment_data =
+                    animation_graph.get_segment_data(animation_graph.get_vertex(&state));
+                let mut target = world.query::<<SpriteTarget as AnimationTarget<S>>::Query>();
+                if let Ok(target) = target.get_mut(world, entity) {
+                    <SpriteTarget as AnimationTarget<S>>::apply(
+                        target,
+                        &state,
+                        segment_data,
+                        frame,
+                    );
+                }
+            });
+        });
+    }
+}
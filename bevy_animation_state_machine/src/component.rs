@@ -1,13 +1,20 @@
-use std::ops::Deref;
-
 use bevy::prelude::*;
 
-use crate::animation_graph::SpriteAnimationGraph;
+use crate::animation_graph::{
+    AnimationGraphError, LoopCount, SpriteAnimationGraph, UnreachableBehaviour,
+};
 
 use crate::graph::Vertex;
+use crate::random::Random;
 use crate::SpriteAnimationState;
 
-#[derive(Component)]
+/// The runtime state of an animated entity.
+/// Vertices are only meaningful for the [`SpriteAnimationGraph`] they were created from, use [`SpriteAnimationSnapshot`] to persist this state across changes to the graph (e.g. in save games).
+/// Animations that are loaded through reflection or serde (e.g. from scenes) cannot know which graph their vertices refer to, so they
+/// restart at their entity's desired state, restore them with [`SpriteAnimation::from_snapshot`] to continue where they left off.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpriteAnimation {
     pub timer: Timer,
     /// the vertex of the current animation state
@@ -16,21 +23,207 @@ pub struct SpriteAnimation {
     /// note that this is in reverse order, the desired vertex is at position 0 (the path is a stack).
     /// The last element in the path is the next vertex to visit.
     pub(crate) desired_path: Vec<Vertex>,
+    /// the index of the current frame relative to the start of the current segment.
+    pub(crate) frame: usize,
+    /// the number of times the current segment has looped (taken a self-transition or started over) since it was entered.
+    pub(crate) loop_count: usize,
+    /// the number of times the current segment plays before the next transition is taken, chosen from its [`crate::LoopCount`] once it is needed.
+    pub(crate) loop_target: Option<usize>,
+    /// the random number generator of this animation, seeded with the entity when the animation is first planned unless it was seeded explicitly.
+    pub(crate) random: Option<Random>,
+    /// the vertex of the segment that finished playing (took a waiting transition) during the last update, if any.
+    pub(crate) finished_vertex: Option<Vertex>,
+    /// the revision of the animation graph that the vertices above refer to, or [`None`] for new animations whose vertices refer to the graph
+    /// as it is when they are added. The vertices of loaded animations are stale (see [`stale_revision`]).
+    #[reflect(ignore, default = "stale_revision")]
+    #[cfg_attr(feature = "serialize", serde(skip, default = "stale_revision"))]
+    pub(crate) graph_revision: Option<usize>,
+    /// whether the path to the desired vertex needs to be planned again even though the desired state has not changed.
+    #[reflect(ignore)]
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub(crate) replan: bool,
 }
 
 impl SpriteAnimation {
+    /// Creates an animation that starts at `initial_vertex`, a vertex of the animation graph as it is when the animation is added to an entity.
     pub fn new(initial_vertex: Vertex, frame_duration: f32) -> Self {
         return SpriteAnimation {
             current_vertex: initial_vertex,
             desired_path: vec![initial_vertex],
+            frame: 0,
+            loop_count: 0,
+            loop_target: None,
+            random: None,
+            finished_vertex: None,
+            graph_revision: None,
+            replan: false,
             timer: Timer::from_seconds(frame_duration, TimerMode::Repeating),
         };
     }
 
+    /// the animation state that is currently playing, this may differ from the entity's desired state while on the way to it.
+    pub fn current_state<'a, S: SpriteAnimationState>(
+        &self,
+        animation_graph: &'a SpriteAnimationGraph<S>,
+    ) -> &'a S {
+        return animation_graph.get_state(self.current_vertex);
+    }
+
+    /// the animation state that the animation is on its way to.
+    pub fn desired_state<'a, S: SpriteAnimationState>(
+        &self,
+        animation_graph: &'a SpriteAnimationGraph<S>,
+    ) -> &'a S {
+        return animation_graph.get_state(self.desired_path[0]);
+    }
+
+    /// the animation states that will be played on the way to the desired state, in the order they will be played (ending with the desired state).
+    pub fn planned_path<S: SpriteAnimationState>(
+        &self,
+        animation_graph: &SpriteAnimationGraph<S>,
+    ) -> Vec<S> {
+        return self
+            .desired_path
+            .iter()
+            .rev()
+            .map(|vertex| *animation_graph.get_state(*vertex))
+            .collect();
+    }
+
+    pub fn current_vertex(&self) -> Vertex {
+        return self.current_vertex;
+    }
+
+    /// the index of the current frame relative to the start of the current segment.
+    pub fn frame(&self) -> usize {
+        return self.frame;
+    }
+
+    /// how far the current segment has played, from 0 at the start of its first frame to 1 at the end of its last frame.
+    pub fn normalized_time<S: SpriteAnimationState>(
+        &self,
+        animation_graph: &SpriteAnimationGraph<S>,
+    ) -> f32 {
+        let segment_length = animation_graph
+            .get_segment_data(self.current_vertex)
+            .segment_frames
+            .len();
+        if segment_length == 0 {
+            return 0.;
+        }
+        return ((self.frame as f32 + self.timer.percent()) / segment_length as f32).min(1.);
+    }
+
+    /// Seeds the random choices of this animation (e.g. random [`crate::LoopCount`]s), so that they are the same every time it is played.
+    /// Animations that are not seeded explicitly are seeded with their entity.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.random = Some(Random::new(seed));
+        return self;
+    }
+
+    /// the number of times the current segment has looped since it was entered.
+    pub fn loop_count(&self) -> usize {
+        return self.loop_count;
+    }
+
+    /// the number of times the current segment plays before the next transition is taken, once it has been chosen
+    /// (when the segment first reaches the frame that the transition waits for).
+    pub fn loop_target(&self) -> Option<usize> {
+        return self.loop_target;
+    }
+
+    /// the number of times the current segment plays before the next transition is taken, choosing it from `loops` if it has not been chosen yet.
+    pub(crate) fn choose_loop_target(&mut self, loops: LoopCount) -> usize {
+        if let Some(loop_target) = self.loop_target {
+            return loop_target;
+        }
+        let loop_target = self
+            .random
+            .get_or_insert(Random::new(0))
+            .range(loops.min, loops.max);
+        self.loop_target = Some(loop_target);
+        return loop_target;
+    }
+
+    /// Decides whether the current state, which is the desired state, loops or plays one of its weighted transitions (see [`crate::TransitionData::weight`])
+    /// next. The path then leads from the chosen state back to the current state, variants that cannot return to it are not played.
+    pub(crate) fn choose_variant<S: SpriteAnimationState>(
+        &mut self,
+        animation_graph: &SpriteAnimationGraph<S>,
+    ) {
+        let total_weight: u64 = animation_graph
+            .variants(self.current_vertex)
+            .map(|(_, weight)| weight as u64)
+            .sum();
+        if total_weight == 0 {
+            return;
+        }
+        let mut choice = self
+            .random
+            .get_or_insert(Random::new(0))
+            .range(0, (total_weight - 1) as usize) as u64;
+        let variant = animation_graph
+            .variants(self.current_vertex)
+            .find(|(_, weight)| {
+                if choice < *weight as u64 {
+                    return true;
+                }
+                choice -= *weight as u64;
+                return false;
+            })
+            .map(|(vertex, _)| vertex);
+        if let Some(variant) = variant.filter(|variant| *variant != self.current_vertex) {
+            if let Some(path) = animation_graph.find_path(variant, self.current_vertex) {
+                self.desired_path = path;
+            }
+        }
+    }
+
+    /// Starts the current segment over, rather than taking the next transition.
+    pub(crate) fn restart_segment(&mut self) {
+        self.frame = 0;
+        self.loop_count += 1;
+    }
+
+    /// the animation state whose segment finished playing during the last update of the animation (by taking a transition that waited for it), if any.
+    pub fn finished_state<'a, S: SpriteAnimationState>(
+        &self,
+        animation_graph: &'a SpriteAnimationGraph<S>,
+    ) -> Option<&'a S> {
+        return self
+            .finished_vertex
+            .map(|vertex| animation_graph.get_state(vertex));
+    }
+
+    /// whether the last frame of the current segment is showing.
+    pub fn is_at_last_frame<S: SpriteAnimationState>(
+        &self,
+        animation_graph: &SpriteAnimationGraph<S>,
+    ) -> bool {
+        let segment_length = animation_graph
+            .get_segment_data(self.current_vertex)
+            .segment_frames
+            .len();
+        return self.frame + 1 == segment_length;
+    }
+
     pub fn next_vertex(&self) -> Vertex {
         return self.desired_path[self.desired_path.len() - 1];
     }
 
+    /// Takes the next transition on the path to `frame` of the next segment, counting the loops of a segment that transitions to itself.
+    pub(crate) fn take_transition(&mut self, frame: usize) {
+        let previous_vertex = self.current_vertex;
+        self.transition();
+        self.frame = frame;
+        self.loop_target = None;
+        if self.current_vertex == previous_vertex {
+            self.loop_count += 1;
+        } else {
+            self.loop_count = 0;
+        }
+    }
+
     pub fn transition(&mut self) {
         if self.desired_path.len() > 1 {
             self.current_vertex = self.desired_path.pop().unwrap();
@@ -39,6 +232,229 @@ impl SpriteAnimation {
             self.current_vertex = self.desired_path[0];
         }
     }
+
+    /// Catches up with states and transitions that were removed from the `animation_graph` since this animation was last updated.
+    /// If the current state was removed, the animation restarts at `desired_vertex`. If the path to the desired vertex is no longer valid
+    /// it is cleared so that it will be re-planned.
+    pub(crate) fn sync_graph<S: SpriteAnimationState>(
+        &mut self,
+        animation_graph: &SpriteAnimationGraph<S>,
+        desired_vertex: Vertex,
+    ) {
+        let revision = self
+            .graph_revision
+            .unwrap_or_else(|| animation_graph.revision());
+        self.graph_revision = Some(animation_graph.revision());
+
+        match animation_graph.update_vertex(self.current_vertex, revision) {
+            Some(vertex) => self.current_vertex = vertex,
+            None => {
+                self.current_vertex = desired_vertex;
+                self.frame = 0;
+                self.loop_count = 0;
+                self.loop_target = None;
+            }
+        }
+        self.finished_vertex = self
+            .finished_vertex
+            .and_then(|vertex| animation_graph.update_vertex(vertex, revision));
+        let desired_path: Option<Vec<Vertex>> = self
+            .desired_path
+            .iter()
+            .map(|vertex| animation_graph.update_vertex(*vertex, revision))
+            .collect();
+        self.desired_path = match desired_path {
+            Some(path) if animation_graph.is_valid_path(self.current_vertex, &path) => path,
+            _ => vec![self.current_vertex],
+        };
+    }
+
+    /// Places the animation at `frame` of `state` immediately, without following any transitions, and restarts the frame timer.
+    /// The path to the entity's desired state is planned again from `state` on the next update, see also
+    /// [`SpriteAnimationCommandsExt::teleport_animation`](crate::SpriteAnimationCommandsExt::teleport_animation).
+    pub fn teleport<S: SpriteAnimationState>(
+        &mut self,
+        animation_graph: &SpriteAnimationGraph<S>,
+        state: &S,
+        frame: usize,
+    ) {
+        let vertex = animation_graph.get_vertex(state);
+        let segment_frames = &animation_graph.get_segment_data(vertex).segment_frames;
+        if segment_frames.is_sized() && frame >= segment_frames.len() {
+            panic!(
+                "Cannot teleport to frame {:?} of {:?}, the segment is not long enough ({:?}).",
+                frame,
+                state,
+                segment_frames.len()
+            );
+        }
+        self.current_vertex = vertex;
+        self.desired_path = vec![vertex];
+        self.frame = frame;
+        self.loop_count = 0;
+        self.loop_target = None;
+        self.graph_revision = Some(animation_graph.revision());
+        self.replan = true;
+        self.timer.reset();
+    }
+
+    /// Plans the path from the current state to `desired_vertex`. If there is no such path, the animation follows the
+    /// [`UnreachableBehaviour`] of the `animation_graph` and `false` is returned.
+    pub(crate) fn plan_path<S: SpriteAnimationState>(
+        &mut self,
+        animation_graph: &SpriteAnimationGraph<S>,
+        desired_vertex: Vertex,
+    ) -> bool {
+        // the next transition may change, so the number of loops is chosen again.
+        self.loop_target = None;
+        if let Some(path) = animation_graph.find_path(self.current_vertex, desired_vertex) {
+            self.desired_path = path;
+            self.transition(); // this has no effect on current_vertex, but prepares the path stack for later use.
+            return true;
+        }
+        match animation_graph.unreachable_behaviour() {
+            UnreachableBehaviour::Panic => {
+                animation_graph.shortest_path(self.current_vertex, desired_vertex);
+            }
+            UnreachableBehaviour::Stay => self.desired_path = vec![self.current_vertex],
+            UnreachableBehaviour::Snap => self.snap(desired_vertex),
+            UnreachableBehaviour::Fallback(state) => match animation_graph.find_vertex(state) {
+                Some(fallback) => match animation_graph.find_path(self.current_vertex, fallback) {
+                    Some(path) => {
+                        self.desired_path = path;
+                        self.transition();
+                    }
+                    None => self.snap(fallback),
+                },
+                // the fallback state has since been removed from the graph
+                None => self.desired_path = vec![self.current_vertex],
+            },
+        }
+        return false;
+    }
+
+    /// jumps directly to the start of `vertex`.
+    fn snap(&mut self, vertex: Vertex) {
+        self.current_vertex = vertex;
+        self.desired_path = vec![vertex];
+        self.frame = 0;
+        self.loop_count = 0;
+        self.loop_target = None;
+    }
+
+    /// Creates a snapshot of this animation that refers to animation states rather than vertices of the `animation_graph`.
+    pub fn snapshot<S: SpriteAnimationState>(
+        &self,
+        animation_graph: &SpriteAnimationGraph<S>,
+    ) -> SpriteAnimationSnapshot<S> {
+        return SpriteAnimationSnapshot {
+            current_state: *animation_graph.get_state(self.current_vertex),
+            desired_path: self
+                .desired_path
+                .iter()
+                .map(|vertex| *animation_graph.get_state(*vertex))
+                .collect(),
+            frame: self.frame,
+            loop_count: self.loop_count,
+            loop_target: self.loop_target,
+            random_state: self.random.map(|random| random.state()),
+            timer: self.timer.clone(),
+        };
+    }
+
+    /// Restores an animation from a snapshot, resolving its animation states against the (possibly modified) `animation_graph`.
+    /// If the snapshot's path is no longer possible in the graph, the path to the entity's desired state is planned again on the next update,
+    /// and if the current segment has become too short for the snapshot's frame, the segment restarts.
+    /// Returns [`AnimationGraphError::UnknownState`] if a state of the snapshot is no longer part of the graph.
+    pub fn from_snapshot<S: SpriteAnimationState>(
+        snapshot: &SpriteAnimationSnapshot<S>,
+        animation_graph: &SpriteAnimationGraph<S>,
+    ) -> Result<Self, AnimationGraphError<S>> {
+        let find_vertex = |state: &S| {
+            animation_graph
+                .find_vertex(state)
+                .ok_or(AnimationGraphError::UnknownState(*state))
+        };
+        let current_vertex = find_vertex(&snapshot.current_state)?;
+        let mut desired_path = snapshot
+            .desired_path
+            .iter()
+            .map(find_vertex)
+            .collect::<Result<Vec<Vertex>, _>>()?;
+        let replan = desired_path.is_empty()
+            || !animation_graph.is_valid_path(current_vertex, &desired_path);
+        if replan {
+            desired_path = vec![current_vertex];
+        }
+        let segment_frames = &animation_graph
+            .get_segment_data(current_vertex)
+            .segment_frames;
+        let frame = if segment_frames.is_sized() && snapshot.frame >= segment_frames.len() {
+            0
+        } else {
+            snapshot.frame
+        };
+        return Ok(SpriteAnimation {
+            current_vertex: current_vertex,
+            desired_path: desired_path,
+            frame: frame,
+            loop_count: snapshot.loop_count,
+            // the number of loops was chosen for the next transition of the snapshot's path.
+            loop_target: if replan { None } else { snapshot.loop_target },
+            random: snapshot.random_state.map(Random::new),
+            finished_vertex: None,
+            graph_revision: Some(animation_graph.revision()),
+            replan: replan,
+            timer: snapshot.timer.clone(),
+        });
+    }
+}
+
+impl Default for SpriteAnimation {
+    fn default() -> Self {
+        return SpriteAnimation {
+            current_vertex: 0,
+            desired_path: vec![0],
+            frame: 0,
+            loop_count: 0,
+            loop_target: None,
+            random: None,
+            finished_vertex: None,
+            graph_revision: None,
+            replan: false,
+            timer: Timer::default(),
+        };
+    }
+}
+
+/// the revision of animations that were loaded through reflection or serde. It is newer than any graph, so every vertex of the animation
+/// is treated as removed from the graph and the animation restarts at its entity's desired state.
+fn stale_revision() -> Option<usize> {
+    return Some(usize::MAX);
+}
+
+/// A copy of the runtime state of a [`SpriteAnimation`] that stores animation states rather than vertices.
+/// Unlike the vertices stored by [`SpriteAnimation`], states remain valid when the animation graph is edited.
+#[derive(Reflect, Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpriteAnimationSnapshot<S: SpriteAnimationState> {
+    /// the current animation state
+    pub current_state: S,
+    /// the path to the desired animation state, see [`SpriteAnimation`] for details.
+    pub desired_path: Vec<S>,
+    /// the index of the current frame relative to the start of the current segment.
+    pub frame: usize,
+    /// the number of times the current segment has looped.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub loop_count: usize,
+    /// the number of times the current segment plays before the next transition is taken, if it has been chosen.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub loop_target: Option<usize>,
+    /// the state of the animation's random number generator, so that a restored animation makes the same random choices as the original would have.
+    /// Restored animations without one are seeded with their entity.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub random_state: Option<u64>,
+    pub timer: Timer,
 }
 
 #[derive(Bundle)]
@@ -48,3 +464,126 @@ pub struct SpriteAnimationBundle<S: SpriteAnimationState> {
     #[bundle()]
     pub sprite_bundle: SpriteSheetBundle,
 }
+
+/// An animated sprite whose segments are made of individual images (see [`crate::SegmentFrames::Images`]) rather than texture atlases.
+#[derive(Bundle)]
+pub struct SpriteImageAnimationBundle<S: SpriteAnimationState> {
+    pub state: S,
+    pub animation: SpriteAnimation,
+    #[bundle()]
+    pub sprite_bundle: SpriteBundle,
+}
+
+/// An animated UI image whose segments are made of texture atlases, this shares the animation graph of sprites with the same animation state.
+#[derive(Bundle)]
+pub struct UiAnimationBundle<S: SpriteAnimationState> {
+    pub state: S,
+    pub animation: SpriteAnimation,
+    #[bundle()]
+    pub image_bundle: AtlasImageBundle,
+}
+
+/// An animated UI image whose segments are made of individual images (see [`crate::SegmentFrames::Images`]).
+#[derive(Bundle)]
+pub struct UiImageAnimationBundle<S: SpriteAnimationState> {
+    pub state: S,
+    pub animation: SpriteAnimation,
+    #[bundle()]
+    pub image_bundle: ImageBundle,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{segment_data, State};
+    use crate::{SpriteAnimationGraphBuilder, TransitionData};
+
+    fn snapshot(current_state: State, desired_path: Vec<State>) -> SpriteAnimationSnapshot<State> {
+        return SpriteAnimationSnapshot {
+            current_state: current_state,
+            desired_path: desired_path,
+            frame: 2,
+            loop_count: 0,
+            loop_target: Some(3),
+            random_state: None,
+            timer: Timer::default(),
+        };
+    }
+
+    #[test]
+    fn snapshots_keep_random_choices() {
+        let animation_graph = SpriteAnimationGraphBuilder::new()
+            .state(State::A, segment_data())
+            .transition(
+                State::A,
+                State::A,
+                TransitionData::wait().with_loops(1..=100),
+            )
+            .build()
+            .unwrap();
+        let mut animation = SpriteAnimation::new(0, 1.).with_seed(7);
+        let loops = animation_graph.get_transition_data((0, 0)).loops;
+        animation.choose_loop_target(loops);
+
+        let mut restored =
+            SpriteAnimation::from_snapshot(&animation.snapshot(&animation_graph), &animation_graph)
+                .unwrap();
+        assert_eq!(restored.loop_target(), animation.loop_target());
+        for _ in 0..10 {
+            animation.loop_target = None;
+            restored.loop_target = None;
+            assert_eq!(
+                restored.choose_loop_target(loops),
+                animation.choose_loop_target(loops)
+            );
+        }
+    }
+
+    #[test]
+    fn from_snapshot_keeps_a_valid_path() {
+        let animation_graph = SpriteAnimationGraphBuilder::new()
+            .state(State::A, segment_data())
+            .state(State::B, segment_data())
+            .transition(State::A, State::B, TransitionData::wait())
+            .build()
+            .unwrap();
+        let animation =
+            SpriteAnimation::from_snapshot(&snapshot(State::A, vec![State::B]), &animation_graph)
+                .unwrap();
+        assert_eq!(animation.planned_path(&animation_graph), vec![State::B]);
+        assert_eq!(animation.frame(), 2);
+        assert!(!animation.replan);
+        assert_eq!(animation.loop_target(), Some(3));
+    }
+
+    #[test]
+    fn from_snapshot_replans_a_path_that_no_longer_exists() {
+        let animation_graph = SpriteAnimationGraphBuilder::new()
+            .state(State::B, segment_data())
+            .state(State::C, segment_data())
+            .transition(State::C, State::B, TransitionData::wait())
+            .build()
+            .unwrap();
+        let animation =
+            SpriteAnimation::from_snapshot(&snapshot(State::B, vec![State::C]), &animation_graph)
+                .unwrap();
+        assert_eq!(animation.current_state(&animation_graph), &State::B);
+        assert_eq!(animation.planned_path(&animation_graph), vec![State::B]);
+        assert!(animation.replan);
+        assert_eq!(animation.loop_target(), None);
+    }
+
+    #[test]
+    fn from_snapshot_reports_removed_states() {
+        let animation_graph = SpriteAnimationGraphBuilder::new()
+            .state(State::B, segment_data())
+            .build()
+            .unwrap();
+        let result =
+            SpriteAnimation::from_snapshot(&snapshot(State::A, vec![State::B]), &animation_graph);
+        assert_eq!(
+            result.err(),
+            Some(AnimationGraphError::UnknownState(State::A))
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::animation_graph::SpriteAnimationGraph;
+use crate::component::SpriteAnimation;
+use crate::SpriteAnimationState;
+
+/// The animation states that entities are playing, as opposed to the states they desire (their `S` component), which can differ while
+/// an animation is on its way to its desired state. This is empty until a [`SpriteAnimationGraph<S>`] resource has been inserted.
+///
+/// ```ignore
+/// fn land(animations: AnimationStates<PlayerState>, mut players: Query<&mut Player>) {
+///     for (entity, _) in animations.finished().filter(|(_, state)| **state == PlayerState::Landing) {
+///         players.get_mut(entity).unwrap().grounded = true;
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct AnimationStates<'w, 's, S: SpriteAnimationState> {
+    animations: Query<'w, 's, (Entity, &'static SpriteAnimation), With<S>>,
+    animation_graph: Option<Res<'w, SpriteAnimationGraph<S>>>,
+}
+
+impl<'w, 's, S: SpriteAnimationState> AnimationStates<'w, 's, S> {
+    /// every animated entity with the animation state it is playing.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &S)> + '_ {
+        return self
+            .animation_graph
+            .iter()
+            .flat_map(move |animation_graph| {
+                self.animations.iter().map(move |(entity, animation)| {
+                    (entity, animation.current_state(animation_graph))
+                })
+            });
+    }
+
+    /// the animation state that `entity` is playing, if it is animated.
+    pub fn get(&self, entity: Entity) -> Option<&S> {
+        let animation_graph = self.animation_graph.as_ref()?;
+        let (_, animation) = self.animations.get(entity).ok()?;
+        return Some(animation.current_state(animation_graph));
+    }
+
+    /// the entities that are playing `state`.
+    pub fn in_state(&self, state: S) -> impl Iterator<Item = Entity> + '_ {
+        return self
+            .iter()
+            .filter(move |(_, current)| **current == state)
+            .map(|(entity, _)| entity);
+    }
+
+    /// whether any entity is playing `state`.
+    pub fn any_in_state(&self, state: S) -> bool {
+        return self.in_state(state).next().is_some();
+    }
+
+    /// the entities whose segment finished playing during the last update of their animation, with the state of the finished segment
+    /// (see [`SpriteAnimation::finished_state`]).
+    pub fn finished(&self) -> impl Iterator<Item = (Entity, &S)> + '_ {
+        return self
+            .animation_graph
+            .iter()
+            .flat_map(move |animation_graph| {
+                self.animations
+                    .iter()
+                    .filter_map(move |(entity, animation)| {
+                        animation
+                            .finished_state(animation_graph)
+                            .map(|state| (entity, state))
+                    })
+            });
+    }
+}
+
+/// A run condition that is true while any entity is playing the animation state `state`.
+///
+/// ```ignore
+/// app.add_systems(Update, shake_camera.run_if(any_entity_in_animation_state(PlayerState::Landing)));
+/// ```
+pub fn any_entity_in_animation_state<S: SpriteAnimationState>(
+    state: S,
+) -> impl FnMut(AnimationStates<S>) -> bool + Clone {
+    return move |animations: AnimationStates<S>| animations.any_in_state(state);
+}
+
+/// A run condition that is true when the segment of the animation state `state` finished playing for any entity during the last update.
+pub fn any_animation_finished<S: SpriteAnimationState>(
+    state: S,
+) -> impl FnMut(AnimationStates<S>) -> bool + Clone {
+    return move |animations: AnimationStates<S>| {
+        animations
+            .finished()
+            .any(|(_, finished)| *finished == state)
+    };
+}
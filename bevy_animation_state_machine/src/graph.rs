@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
 use std::fmt::Debug;
 
 #[derive(Debug)]
@@ -9,83 +9,219 @@ pub enum GraphError {
 pub type Vertex = usize;
 pub type Edge = (Vertex, Vertex);
 
+/// Edge metadata that orders the outgoing edges of a vertex.
+pub trait Priority {
+    /// paths over edges with a higher priority are preferred by [`Graph::shortest_path`] when there are several equally short paths,
+    /// the priorities of the paths are compared edge by edge from the start of the paths.
+    fn priority(&self) -> i32;
+}
+
+/// A directed graph over contiguous vertices `0..n`.
+/// Vertex metadata is stored densely by vertex and edges are indexed densely by their source and target vertices, so that metadata lookups
+/// are array loads. For path finding, the outgoing edges of each vertex are also stored contiguously in compressed sparse row form,
+/// which is rebuilt once for every batch of added edges (see [`Graph::add_edges`]).
 #[derive(Debug, Clone)]
 pub struct Graph<V, E> {
-    edges: HashMap<Edge, E>,   // Metadata for edges
-    verts: HashMap<Vertex, V>, // Metadata for vertices
-    adjacency: HashMap<Vertex, HashSet<Vertex>>,
+    verts: Vec<V>,                         // Metadata for vertices, indexed by vertex
+    edges: Vec<(Edge, E)>,                 // Metadata for edges, in the order they were added
+    edge_indices: Vec<Vec<Option<usize>>>, // the index into `edges` of the edge `(from, to)` is `edge_indices[from][to]`, rows grow as needed
+    offsets: Vec<usize>, // the outgoing edges of vertex `v` are `adjacency[offsets[v]..offsets[v + 1]]`
+    adjacency: Vec<(Vertex, usize)>, // (neighbour, index into `edges`), grouped by source vertex
 }
 
-impl<V, E> Graph<V, E> {
+impl<V, E: Priority> Graph<V, E> {
     pub fn new() -> Self {
         Graph {
-            edges: HashMap::new(),
-            verts: HashMap::new(),
-            adjacency: HashMap::new(),
+            verts: Vec::new(),
+            edges: Vec::new(),
+            edge_indices: Vec::new(),
+            offsets: vec![0],
+            adjacency: Vec::new(),
         }
     }
 
     pub fn get_vertex_metadata(&self, vertex: Vertex) -> Option<&V> {
-        self.verts.get(&vertex)
+        self.verts.get(vertex)
+    }
+
+    pub fn get_vertex_metadata_mut(&mut self, vertex: Vertex) -> Option<&mut V> {
+        self.verts.get_mut(vertex)
     }
 
     pub fn get_edge_metadata(&self, edge: (Vertex, Vertex)) -> Option<&E> {
-        self.edges.get(&edge)
+        self.edge_index(edge).map(|index| &self.edges[index].1)
+    }
+
+    pub fn get_edge_metadata_mut(&mut self, edge: (Vertex, Vertex)) -> Option<&mut E> {
+        self.edge_index(edge).map(|index| &mut self.edges[index].1)
     }
 
     pub fn add_node(&mut self, node: Vertex, metadata: V) {
-        self.verts.insert(node, metadata);
-        self.adjacency.insert(node, HashSet::new());
+        if node != self.verts.len() {
+            panic!(
+                "Vertex {} cannot be added, vertices must be added in order (the next vertex is {}).",
+                node,
+                self.verts.len()
+            );
+        }
+        self.verts.push(metadata);
+        // the new vertex has no outgoing edges yet
+        self.edge_indices.push(Vec::new());
+        self.offsets.push(self.adjacency.len());
     }
 
+    /// Adds an edge, or replaces the metadata of an existing edge. Use [`Graph::add_edges`] to add many edges at once.
     pub fn add_edge(&mut self, edge: (Vertex, Vertex), metadata: E) {
-        if !self.verts.contains_key(&edge.0) {
-            panic!("Vertex {} not found", edge.0);
+        self.add_edges(std::iter::once((edge, metadata)));
+    }
+
+    /// Adds (or replaces the metadata of) several edges, the compressed adjacency is only rebuilt once for all of them.
+    pub fn add_edges(&mut self, edges: impl IntoIterator<Item = (Edge, E)>) {
+        for (edge, metadata) in edges {
+            if !self.contains_vertex(edge.0) {
+                panic!("Vertex {} not found", edge.0);
+            }
+            if !self.contains_vertex(edge.1) {
+                panic!("Vertex {} not found", edge.1);
+            }
+
+            match self.edge_index(edge) {
+                Some(index) => self.edges[index].1 = metadata,
+                None => {
+                    let row = &mut self.edge_indices[edge.0];
+                    if row.len() <= edge.1 {
+                        row.resize(edge.1 + 1, None);
+                    }
+                    row[edge.1] = Some(self.edges.len());
+                    self.edges.push((edge, metadata));
+                }
+            }
         }
-        if !self.verts.contains_key(&edge.1) {
-            panic!("Vertex {} not found", edge.1);
+        // the priority of the edges may have changed
+        self.rebuild_adjacency();
+    }
+
+    /// every edge with its metadata, in the order they were added.
+    pub fn edges(&self) -> impl Iterator<Item = (Edge, &E)> {
+        self.edges.iter().map(|(edge, metadata)| (*edge, metadata))
+    }
+
+    /// the outgoing edges of `vertex` with their metadata, in the order of their priority.
+    pub fn outgoing_edges(&self, vertex: Vertex) -> impl Iterator<Item = (Vertex, &E)> {
+        self.neighbors(vertex)
+            .iter()
+            .map(|(neighbor, index)| (*neighbor, &self.edges[*index].1))
+    }
+
+    /// the outgoing edges of `vertex` as (neighbour, edge index) pairs.
+    fn neighbors(&self, vertex: Vertex) -> &[(Vertex, usize)] {
+        &self.adjacency[self.offsets[vertex]..self.offsets[vertex + 1]]
+    }
+
+    fn edge_index(&self, edge: Edge) -> Option<usize> {
+        self.edge_indices
+            .get(edge.0)?
+            .get(edge.1)
+            .copied()
+            .flatten()
+    }
+
+    /// recomputes the dense edge index from the edge list, this is only needed when edges are removed (which shifts the edge list).
+    fn rebuild_edge_indices(&mut self) {
+        self.edge_indices = vec![Vec::new(); self.verts.len()];
+        for (index, ((from, to), _)) in self.edges.iter().enumerate() {
+            let row = &mut self.edge_indices[*from];
+            if row.len() <= *to {
+                row.resize(to + 1, None);
+            }
+            row[*to] = Some(index);
         }
+    }
 
-        self.edges.insert(edge, metadata);
-        if let Some(neighbors) = self.adjacency.get_mut(&edge.0) {
-            neighbors.insert(edge.1);
+    /// recomputes the compressed adjacency from the edge list, this is only needed when edges change.
+    /// The outgoing edges of each vertex are ordered by priority (highest first) and then by the order in which they were added.
+    fn rebuild_adjacency(&mut self) {
+        let mut offsets = vec![0; self.verts.len() + 1];
+        for ((from, _), _) in &self.edges {
+            offsets[from + 1] += 1;
+        }
+        for vertex in 0..self.verts.len() {
+            offsets[vertex + 1] += offsets[vertex];
+        }
+        // place each edge after those of the same source vertex that were added before it.
+        let mut next = offsets.clone();
+        let mut adjacency = vec![(0, 0); self.edges.len()];
+        for (index, ((from, to), _)) in self.edges.iter().enumerate() {
+            adjacency[next[*from]] = (*to, index);
+            next[*from] += 1;
         }
+        for vertex in 0..self.verts.len() {
+            adjacency[offsets[vertex]..offsets[vertex + 1]]
+                .sort_by_key(|(_, index)| Reverse(self.edges[*index].1.priority()));
+        }
+        self.offsets = offsets;
+        self.adjacency = adjacency;
     }
 
+    /// Finds a path with the fewest edges using a breadth first search. Among equally short paths, the path whose edges have the highest
+    /// priorities (see [`Priority`]) is chosen, comparing the priorities of the paths edge by edge from the start. Paths whose priorities
+    /// are all equal are chosen by the order in which their edges were added, so that the same path is always chosen.
     pub fn shortest_path(&self, start: Vertex, end: Vertex) -> Result<Vec<Vertex>, GraphError> {
-        if !self.verts.contains_key(&start) || !self.verts.contains_key(&end) {
+        if !self.contains_vertex(start) || !self.contains_vertex(end) {
             return Err(GraphError::NoSuchPath(format!(
                 "Either vertex {} or {} doesn't exist.",
                 start, end
             )));
         }
 
-        let mut visited = HashSet::new();
-        let mut queue = VecDeque::new();
-        let mut predecessors: HashMap<Vertex, Vertex> = HashMap::new();
+        let mut visited = vec![false; self.verts.len()];
+        let mut predecessors: Vec<Option<Vertex>> = vec![None; self.verts.len()];
+        // the best paths to the vertices of a layer are ranked against each other (lower is better, equally good paths share a rank).
+        // the best path to a vertex of the next layer continues the best ranked path over the edge with the highest priority.
+        let mut ranks = vec![0; self.verts.len()];
+        let mut keys: Vec<Option<(usize, Reverse<i32>)>> = vec![None; self.verts.len()];
 
-        visited.insert(start);
-        queue.push_back(start);
+        visited[start] = true;
+        let mut layer = vec![start];
 
-        while let Some(current) = queue.pop_front() {
-            if current == end {
+        while !layer.is_empty() {
+            if visited[end] {
                 let mut path = vec![end];
-                while let Some(&node) = predecessors.get(&path[path.len() - 1]) {
+                while let Some(node) = predecessors[path[path.len() - 1]] {
                     path.push(node);
                 }
                 //path.reverse();
                 return Ok(path);
             }
 
-            if let Some(neighbors) = self.adjacency.get(&current) {
-                for &neighbor in neighbors {
-                    if !visited.contains(&neighbor) {
-                        visited.insert(neighbor);
-                        queue.push_back(neighbor);
-                        predecessors.insert(neighbor, current);
+            let mut next_layer = Vec::new();
+            for &current in &layer {
+                for &(neighbor, index) in self.neighbors(current) {
+                    if visited[neighbor] {
+                        continue;
+                    }
+                    let key = (ranks[current], Reverse(self.edges[index].1.priority()));
+                    match keys[neighbor] {
+                        None => next_layer.push(neighbor),
+                        // the path found first is kept among equally good paths
+                        Some(best) if key >= best => continue,
+                        Some(_) => {}
                     }
+                    keys[neighbor] = Some(key);
+                    predecessors[neighbor] = Some(current);
                 }
             }
+
+            next_layer.sort_by_key(|vertex| keys[*vertex]);
+            let mut rank = 0;
+            for (i, vertex) in next_layer.iter().enumerate() {
+                if i > 0 && keys[*vertex] != keys[next_layer[i - 1]] {
+                    rank += 1;
+                }
+                ranks[*vertex] = rank;
+                visited[*vertex] = true;
+            }
+            layer = next_layer;
         }
 
         Err(GraphError::NoSuchPath(format!(
@@ -95,8 +231,130 @@ impl<V, E> Graph<V, E> {
     }
 
     pub fn contains_vertex(&self, vertex: Vertex) -> bool {
-        return self.adjacency.contains_key(&vertex);
+        return vertex < self.verts.len();
+    }
+
+    pub fn contains_edge(&self, edge: Edge) -> bool {
+        return self.edge_index(edge).is_some();
+    }
+
+    pub fn remove_edge(&mut self, edge: Edge) -> Option<E> {
+        let index = self.edge_index(edge)?;
+        let (_, metadata) = self.edges.remove(index);
+        self.rebuild_edge_indices();
+        self.rebuild_adjacency();
+        return Some(metadata);
+    }
+
+    /// Removes a vertex and all of its edges. Vertices are contiguous, so every vertex greater than
+    /// the removed vertex is relabelled to the vertex before it.
+    pub fn remove_node(&mut self, node: Vertex) -> Option<V> {
+        if !self.contains_vertex(node) {
+            return None;
+        }
+        let metadata = self.verts.remove(node);
+        let relabel = |vertex: Vertex| if vertex > node { vertex - 1 } else { vertex };
+
+        self.edges = self
+            .edges
+            .drain(..)
+            .filter(|(edge, _)| edge.0 != node && edge.1 != node)
+            .map(|(edge, metadata)| ((relabel(edge.0), relabel(edge.1)), metadata))
+            .collect();
+        self.rebuild_edge_indices();
+        self.rebuild_adjacency();
+        return Some(metadata);
     }
 
     // Add methods for querying and modifying the graph as needed.
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// edge metadata that is only its priority.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Weight(i32);
+
+    impl Priority for Weight {
+        fn priority(&self) -> i32 {
+            return self.0;
+        }
+    }
+
+    fn build(vertices: usize, edges: &[(Edge, i32)]) -> Graph<(), Weight> {
+        let mut graph = Graph::new();
+        for vertex in 0..vertices {
+            graph.add_node(vertex, ());
+        }
+        graph.add_edges(
+            edges
+                .iter()
+                .map(|(edge, priority)| (*edge, Weight(*priority))),
+        );
+        return graph;
+    }
+
+    #[test]
+    fn edges_are_looked_up_by_source_and_target() {
+        let mut graph = build(3, &[((0, 1), 1), ((1, 2), 2), ((2, 0), 3)]);
+        assert_eq!(graph.get_edge_metadata((1, 2)), Some(&Weight(2)));
+        assert_eq!(graph.get_edge_metadata((2, 1)), None);
+        assert_eq!(graph.get_edge_metadata((5, 0)), None);
+
+        // adding an existing edge replaces its metadata
+        graph.add_edge((1, 2), Weight(4));
+        assert_eq!(graph.get_edge_metadata((1, 2)), Some(&Weight(4)));
+        assert_eq!(graph.edges().count(), 3);
+    }
+
+    #[test]
+    fn removing_edges_and_vertices_keeps_the_index() {
+        let mut graph = build(3, &[((0, 1), 1), ((1, 2), 2), ((2, 0), 3), ((0, 2), 4)]);
+        assert_eq!(graph.remove_edge((0, 1)), Some(Weight(1)));
+        assert_eq!(graph.get_edge_metadata((0, 1)), None);
+        assert_eq!(graph.get_edge_metadata((0, 2)), Some(&Weight(4)));
+        assert_eq!(graph.remove_edge((0, 1)), None);
+
+        // vertex 2 becomes vertex 1
+        assert_eq!(graph.remove_node(1), Some(()));
+        assert_eq!(graph.get_edge_metadata((1, 0)), Some(&Weight(3)));
+        assert_eq!(graph.get_edge_metadata((0, 1)), Some(&Weight(4)));
+        assert_eq!(graph.outgoing_edges(0).count(), 1);
+        assert_eq!(graph.shortest_path(0, 1).unwrap(), vec![1, 0]);
+    }
+
+    #[test]
+    fn shortest_path_has_the_fewest_edges() {
+        // 0 -> 1 -> 2 -> 3 and 0 -> 3 with a lower priority
+        let graph = build(4, &[((0, 1), 10), ((1, 2), 10), ((2, 3), 10), ((0, 3), 0)]);
+        assert_eq!(graph.shortest_path(0, 3).unwrap(), vec![3, 0]);
+        assert_eq!(graph.shortest_path(0, 0).unwrap(), vec![0]);
+        assert!(graph.shortest_path(3, 0).is_err());
+    }
+
+    #[test]
+    fn shortest_path_prefers_the_first_edge_with_a_higher_priority() {
+        // 0 -> 1 -> 3 and 0 -> 2 -> 3, where only the first edges differ
+        let graph = build(4, &[((0, 1), 0), ((0, 2), 1), ((1, 3), 0), ((2, 3), 0)]);
+        assert_eq!(graph.shortest_path(0, 3).unwrap(), vec![3, 2, 0]);
+
+        // a higher priority later on does not outweigh the first edge
+        let graph = build(4, &[((0, 1), 1), ((0, 2), 0), ((1, 3), 0), ((2, 3), 10)]);
+        assert_eq!(graph.shortest_path(0, 3).unwrap(), vec![3, 1, 0]);
+    }
+
+    #[test]
+    fn shortest_path_breaks_ties_at_later_edges() {
+        // the first edges are equally good, the second edge through 2 has the higher priority
+        let graph = build(4, &[((0, 1), 0), ((0, 2), 0), ((1, 3), 0), ((2, 3), 10)]);
+        assert_eq!(graph.shortest_path(0, 3).unwrap(), vec![3, 2, 0]);
+
+        // with equal priorities, the edges that were added first are taken
+        let graph = build(4, &[((0, 1), 0), ((0, 2), 0), ((1, 3), 0), ((2, 3), 0)]);
+        assert_eq!(graph.shortest_path(0, 3).unwrap(), vec![3, 1, 0]);
+        let graph = build(4, &[((0, 2), 0), ((0, 1), 0), ((1, 3), 0), ((2, 3), 0)]);
+        assert_eq!(graph.shortest_path(0, 3).unwrap(), vec![3, 2, 0]);
+    }
+}
@@ -1,15 +1,34 @@
 mod animation_graph;
+mod builder;
+mod commands;
 mod component;
+mod condition;
 mod graph;
+mod plugin;
+mod random;
 mod system;
+mod target;
+#[cfg(test)]
+mod test_utils;
 
 pub use animation_graph::{
-    SegmentBehaviour, SegmentData, SegmentInterval, SpriteAnimationGraph, TransitionBehaviour,
-    TransitionData,
+    AnimationGraphError, AnimationPathError, Frame, LoopCount, SegmentBehaviour, SegmentData,
+    SegmentFrames, SegmentGrid, SegmentInterval, SpriteAnimationGraph, TransitionBehaviour,
+    TransitionData, UnreachableBehaviour,
 };
-pub use component::{SpriteAnimation, SpriteAnimationBundle};
+pub use builder::SpriteAnimationGraphBuilder;
+pub use commands::SpriteAnimationCommandsExt;
+pub use component::{
+    SpriteAnimation, SpriteAnimationBundle, SpriteAnimationSnapshot, SpriteImageAnimationBundle,
+    UiAnimationBundle, UiImageAnimationBundle,
+};
+pub use condition::{any_animation_finished, any_entity_in_animation_state, AnimationStates};
+pub use plugin::{AnimationTargetPlugin, SpriteAnimationPlugin};
+
+pub use system::{advance_frames, apply_frames, plan_paths, resolve_segments};
+pub use target::{AnimationTarget, SpriteTarget};
 
-pub use system::animation;
+pub use bevy_animation_state_machine_macros::{animation_graph, SpriteAnimationGraph};
 
 pub trait SpriteAnimationState:
     Eq + PartialEq + std::hash::Hash + Copy + std::fmt::Debug + bevy::prelude::Component
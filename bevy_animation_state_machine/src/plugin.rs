@@ -0,0 +1,79 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::animation_graph::{
+    AnimationPathError, LoopCount, SegmentBehaviour, SegmentData, SegmentFrames, SegmentGrid,
+    SegmentInterval, SpriteAnimationGraph, TransitionBehaviour, TransitionData,
+};
+use crate::component::SpriteAnimation;
+use crate::system::{advance_frames, apply_frames, plan_paths, resolve_segments};
+use crate::target::{AnimationTarget, SpriteTarget};
+use crate::SpriteAnimationState;
+
+/// Adds the systems that animate the animation state `S`: [`resolve_segments`], [`plan_paths`], [`advance_frames`] and [`apply_frames`] for sprites
+/// and UI images, and registers the animation types for reflection. The systems will only run once a [`SpriteAnimationGraph<S>`] resource has been inserted.
+pub struct SpriteAnimationPlugin<S: SpriteAnimationState> {
+    _state: PhantomData<S>,
+}
+
+impl<S: SpriteAnimationState> Default for SpriteAnimationPlugin<S> {
+    fn default() -> Self {
+        return Self {
+            _state: PhantomData,
+        };
+    }
+}
+
+impl<S: SpriteAnimationState> Plugin for SpriteAnimationPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SpriteAnimation>()
+            .register_type::<SegmentData>()
+            .register_type::<SegmentFrames>()
+            .register_type::<SegmentInterval>()
+            .register_type::<SegmentGrid>()
+            .register_type::<SegmentBehaviour>()
+            .register_type::<TransitionData>()
+            .register_type::<TransitionBehaviour>()
+            .register_type::<LoopCount>()
+            .add_event::<AnimationPathError<S>>()
+            .add_systems(
+                Update,
+                (
+                    resolve_segments::<S>,
+                    plan_paths::<S>,
+                    advance_frames::<S>,
+                    apply_frames::<S, SpriteTarget>,
+                )
+                    .chain()
+                    .run_if(resource_exists::<SpriteAnimationGraph<S>>()),
+            );
+    }
+}
+
+/// Shows the frames of animations with the state `S` on the [`AnimationTarget`] `T`, in addition to sprites and UI images.
+/// This requires the [`SpriteAnimationPlugin<S>`].
+pub struct AnimationTargetPlugin<S: SpriteAnimationState, T: AnimationTarget<S>> {
+    _state: PhantomData<S>,
+    _target: PhantomData<T>,
+}
+
+impl<S: SpriteAnimationState, T: AnimationTarget<S>> Default for AnimationTargetPlugin<S, T> {
+    fn default() -> Self {
+        return Self {
+            _state: PhantomData,
+            _target: PhantomData,
+        };
+    }
+}
+
+impl<S: SpriteAnimationState, T: AnimationTarget<S>> Plugin for AnimationTargetPlugin<S, T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            apply_frames::<S, T>
+                .after(advance_frames::<S>)
+                .run_if(resource_exists::<SpriteAnimationGraph<S>>()),
+        );
+    }
+}
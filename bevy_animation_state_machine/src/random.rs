@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+/// A small seedable random number generator (SplitMix64) that each animation carries, so that the random choices of an animation
+/// are reproducible from its seed and independent of the order in which entities are updated.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub(crate) fn new(seed: u64) -> Self {
+        return Random { state: seed };
+    }
+
+    /// the state of the generator, a generator created with this state as its seed continues where this one is.
+    pub(crate) fn state(&self) -> u64 {
+        return self.state;
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+
+    /// a number between `min` and `max` (inclusive). Nothing is drawn if there is only one such number.
+    pub(crate) fn range(&mut self, min: usize, max: usize) -> usize {
+        if min >= max {
+            return min;
+        }
+        let span = (max - min) as u128 + 1;
+        return min + ((self.next_u64() as u128 * span) >> 64) as usize;
+    }
+}
@@ -1,97 +1,420 @@
+use std::sync::Mutex;
+
 use bevy::prelude::*;
 
 use crate::component::SpriteAnimation;
-use crate::{SpriteAnimationGraph, SpriteAnimationState, TransitionBehaviour};
-
-pub fn animation<S: SpriteAnimationState>(
-    mut query: Query<(
-        &S,
-        &mut SpriteAnimation,
-        &mut TextureAtlasSprite,
-        &mut Handle<TextureAtlas>,
-    )>,
-    time: Res<Time>,
+use crate::random::Random;
+use crate::target::AnimationTarget;
+use crate::{
+    AnimationGraphError, AnimationPathError, SpriteAnimationGraph, SpriteAnimationState,
+    TransitionBehaviour,
+};
+
+/// plans the path of every animation whose desired state changed (or whose path was invalidated by a change to the graph) to the desired state.
+/// Unreachable desired states are handled according to the graph's [`crate::UnreachableBehaviour`] and reported with an [`AnimationPathError`].
+pub fn plan_paths<S: SpriteAnimationState>(
+    mut query: Query<(Entity, Ref<S>, &mut SpriteAnimation)>,
     animation_graph: Res<SpriteAnimationGraph<S>>,
+    mut path_errors: EventWriter<AnimationPathError<S>>,
 ) {
-    for (state, mut animation, mut sprite, mut atlas) in &mut query {
-        // TODO rather than always querying with sprite/ atlas as mutable, better may be to use run critera and a system
-        // that only mutates them if `animation` requires it. This will free up other system on sprite and atlas to run async.
+    // entities are planned in parallel, everything done for an entity (including logging and errors) must be thread-safe.
+    let unreachable = Mutex::new(Vec::new());
+    query.par_iter_mut().for_each_mut(|(entity, state, mut animation)| {
+        // the path only needs to change when the desired state or the graph changes, the animation is otherwise left untouched.
+        if !state.is_changed()
+            && !animation.is_added()
+            && !animation.replan
+            && animation.graph_revision == Some(animation_graph.revision())
+        {
+            return;
+        }
+
+        // all events logged while updating this entity will include the entity as a field.
+        #[cfg(feature = "debug")]
+        let _span = debug_span!("plan_paths", entity = ?entity).entered();
 
-        let desired_vertex = animation_graph.get_vertex(state);
+        if animation.random.is_none() {
+            animation.random = Some(Random::new(entity.to_bits()));
+        }
+        let desired_vertex = animation_graph.get_vertex(&state);
+        animation.replan = false;
+        // new animations refer to the vertices of the graph as it is now, rather than to the graph before any earlier changes.
+        if animation.graph_revision.is_none() {
+            animation.graph_revision = Some(animation_graph.revision());
+        }
+        // states or transitions may have been removed from the graph since the last update.
+        if animation.graph_revision != Some(animation_graph.revision()) {
+            animation.sync_graph(&animation_graph, desired_vertex);
+        }
         // check if the desired vertex changed, update the path to it if so
         // note that the path is in reverse order! desired is at element 0
         if animation.desired_path[0] != desired_vertex {
-            animation.desired_path =
-                animation_graph.shortest_path(animation.current_vertex, desired_vertex);
-            animation.transition(); // this has no effect on current_vertex, but prepares the path stack for later use.
+            let current = *animation_graph.get_state(animation.current_vertex);
+            if !animation.plan_path(&animation_graph, desired_vertex) {
+                warn!(
+                    "Animation state {:?} of {:?} cannot be reached from {:?}, following {:?}.",
+                    *state,
+                    entity,
+                    current,
+                    animation_graph.unreachable_behaviour()
+                );
+                unreachable.lock().unwrap().push(AnimationPathError {
+                    entity: entity,
+                    current: current,
+                    desired: *state,
+                });
+            }
+
+            #[cfg(feature = "debug")]
+            debug!(
+                current = ?animation_graph.get_state(animation.current_vertex),
+                desired = ?animation_graph.get_state(desired_vertex),
+                path = ?animation.desired_path.iter().rev().map(|vertex| animation_graph.get_state(*vertex)).collect::<Vec<_>>(),
+                "recomputed animation path"
+            );
         }
+    });
+    path_errors.send_batch(unreachable.into_inner().unwrap());
+}
 
-        let mut next_vertex = animation.next_vertex();
+/// follows the path of every animation, taking immediate transitions and playing out elapsed frames.
+/// Animations are only marked as changed when their state or frame changes, so that [`apply_frames`] only shows frames that changed.
+pub fn advance_frames<S: SpriteAnimationState>(
+    mut query: Query<(Entity, &mut SpriteAnimation), With<S>>,
+    time: Res<Time>,
+    animation_graph: Res<SpriteAnimationGraph<S>>,
+) {
+    let delta = time.delta();
+    query
+        .par_iter_mut()
+        .for_each_mut(|(_entity, mut animation)| {
+            #[cfg(feature = "debug")]
+            let _span = debug_span!("advance_frames", entity = ?_entity).entered();
 
-        let mut segment_data = animation_graph.get_segment_data(animation.current_vertex);
-        let mut transition_data =
-            animation_graph.get_transition_data((animation.current_vertex, next_vertex));
+            let shown = (animation.current_vertex, animation.frame);
+            let animation_state = animation.bypass_change_detection();
+            animation_state.finished_vertex = None;
 
-        println!(
-            "{:?} {:?} {:?}",
-            animation_graph.get_state(desired_vertex),
-            animation_graph.get_state(animation.current_vertex),
-            animation_graph.get_state(next_vertex),
-        );
+            // check whether to immediately transition to the next animation state.
+            transition_immediate(animation_state, &animation_graph);
 
-        // check whether to immediately transition to the next animation state.
-        while transition_data.transition_behaviour == TransitionBehaviour::Immediate {
-            assert!(animation.current_vertex != next_vertex); // this should not be possible due to checks when defining the animation graph.
+            // otherwise, we are waiting for some frames to finish. The timer may have finished more than once
+            // if the last update took longer than a frame, in which case each of the elapsed frames is played out in turn.
+            animation_state.timer.tick(delta);
+            for _ in 0..animation_state.timer.times_finished_this_tick() {
+                advance_frame(animation_state, &animation_graph);
+                transition_immediate(animation_state, &animation_graph);
+            }
 
-            // transition immediately!
-            animation.transition();
+            if (animation_state.current_vertex, animation_state.frame) != shown {
+                animation.set_changed();
+            }
+        });
+}
 
-            // TODO these will be done in a seperate system whose run critera is a change in animation vertex.
-            *atlas = animation_graph.get_atlas(animation.current_vertex).clone();
-            sprite.index =
-                segment_data.segment_interval.start + transition_data.transition_to_frame;
+/// the animated entities with the animation target `T` whose animation changed.
+type ChangedTargets<'w, 's, S, T> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static SpriteAnimation,
+        <T as AnimationTarget<S>>::Query,
+    ),
+    (With<S>, Changed<SpriteAnimation>),
+>;
 
-            next_vertex = animation.next_vertex();
-            segment_data = animation_graph.get_segment_data(animation.current_vertex);
-            transition_data =
-                animation_graph.get_transition_data((animation.current_vertex, next_vertex));
+/// shows the current frame of every animated entity with the animation target `T` whose animation changed, see [`AnimationTarget`].
+pub fn apply_frames<S: SpriteAnimationState, T: AnimationTarget<S>>(
+    mut query: ChangedTargets<S, T>,
+    animation_graph: Res<SpriteAnimationGraph<S>>,
+) {
+    query
+        .par_iter_mut()
+        .for_each_mut(|(_entity, animation, target)| {
+            #[cfg(feature = "debug")]
+            let _span = debug_span!("apply_frames", entity = ?_entity).entered();
 
-            println!(
-                "immediate {:?} {:?} {:?}",
-                animation_graph.get_state(desired_vertex),
+            T::apply(
+                target,
                 animation_graph.get_state(animation.current_vertex),
-                animation_graph.get_state(next_vertex),
+                animation_graph.get_segment_data(animation.current_vertex),
+                animation.frame,
             );
+        });
+}
+
+/// resolves and validates segments against their texture atlases once they have loaded (see [`SpriteAnimationGraph::resolve_segments`]),
+/// logging segments that do not fit their atlas. This only needs to happen when the graph changes or a texture atlas is loaded.
+pub fn resolve_segments<S: SpriteAnimationState>(
+    mut animation_graph: ResMut<SpriteAnimationGraph<S>>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut atlas_events: EventReader<AssetEvent<TextureAtlas>>,
+    mut reported: Local<Vec<AnimationGraphError<S>>>,
+) {
+    let atlas_changed = !atlas_events.is_empty();
+    atlas_events.clear();
+    if animation_graph.is_changed() {
+        reported.clear();
+    } else if !atlas_changed {
+        return;
+    }
+    // resolving does not change how the graph animates, so it should not count as a change to the graph.
+    for error in animation_graph
+        .bypass_change_detection()
+        .resolve_segments(&atlases)
+    {
+        // segments are validated again whenever an atlas loads, each problem is only logged once.
+        if !reported.contains(&error) {
+            error!("{}", error);
+            reported.push(error);
         }
+    }
+}
+
+/// follows every [`TransitionBehaviour::Immediate`] transition on the path to the desired vertex, stopping at the first transition that needs to wait.
+fn transition_immediate<S: SpriteAnimationState>(
+    animation: &mut SpriteAnimation,
+    animation_graph: &SpriteAnimationGraph<S>,
+) {
+    loop {
+        let next_vertex = animation.next_vertex();
+        let transition_data =
+            animation_graph.get_transition_data((animation.current_vertex, next_vertex));
+        if transition_data.transition_behaviour != TransitionBehaviour::Immediate {
+            return;
+        }
+        assert!(animation.current_vertex != next_vertex); // this should not be possible due to checks when defining the animation graph.
+
+        // transition immediately! The current segment is part way through its current frame.
+        #[cfg(feature = "debug")]
+        let previous_vertex = animation.current_vertex;
+        let frame = transition_data.to_frame(
+            animation.normalized_time(animation_graph),
+            animation_graph
+                .get_segment_data(next_vertex)
+                .segment_frames
+                .len(),
+        );
+        animation.take_transition(frame);
+
+        #[cfg(feature = "debug")]
+        debug!(
+            from = ?animation_graph.get_state(previous_vertex),
+            to = ?animation_graph.get_state(animation.current_vertex),
+            frame = animation.frame,
+            "immediate animation transition"
+        );
+    }
+}
 
-        // otherwise, we are waiting for some frames to finish
-        if let TransitionBehaviour::Wait(wait_index) = transition_data.transition_behaviour {
-            animation.timer.tick(time.delta());
-            if animation.timer.just_finished() {
-                let segment_start_index = segment_data.segment_interval.start;
-                let segment_length = segment_data.segment_interval.length;
-                // use the waiting index to determine whether the frame has finished. If the waiting index >= the segment length then take the last frame as the one to wait for.
-                let finished_index = segment_start_index + (segment_length - 1).min(wait_index);
-
-                if sprite.index != finished_index {
-                    sprite.index = segment_start_index
-                        + (sprite.index + 1 - segment_start_index) % segment_length;
-                } else {
-                    // this segment has finished, transition to the next vertex on the path to desired_vertex
-                    animation.transition();
-                    // TODO these will be done in a seperate system whose run critera is a change in animation vertex.
-                    segment_data = animation_graph.get_segment_data(animation.current_vertex); // get new segment data
-                    *atlas = segment_data.texture_atlas_handle.clone();
-                    // println!(
-                    //     "transition to {:?}{:?} {:?}",
-                    //     animation_graph.get_state(animation.current_vertex),
-                    //     segment_data.segment_interval.start,
-                    //     transition_data.transition_to_frame
-                    // );
-                    sprite.index =
-                        segment_data.segment_interval.start + transition_data.transition_to_frame;
-                }
+/// plays a single frame of the current segment. If the segment has reached a frame that the next waiting transition is waiting for
+/// (see [`TransitionBehaviour::exit_frames`]), the transition is taken instead.
+fn advance_frame<S: SpriteAnimationState>(
+    animation: &mut SpriteAnimation,
+    animation_graph: &SpriteAnimationGraph<S>,
+) {
+    let mut transition_data =
+        animation_graph.get_transition_data((animation.current_vertex, animation.next_vertex()));
+    let segment_length = animation_graph
+        .get_segment_data(animation.current_vertex)
+        .segment_frames
+        .len();
+    // the length of the segment is not known until its texture atlas has loaded.
+    if segment_length == 0 {
+        return;
+    }
+    // the frames after which the transition may be taken are resolved against the length of the segment.
+    if let Some(exit_frames) = transition_data
+        .transition_behaviour
+        .exit_frames(segment_length)
+    {
+        if !exit_frames.contains(&animation.frame) {
+            animation.frame = (animation.frame + 1) % segment_length;
+        } else if animation.loop_count + 1 < animation.choose_loop_target(transition_data.loops) {
+            // the segment has to play again before the transition is taken.
+            animation.finished_vertex = Some(animation.current_vertex);
+            animation.restart_segment();
+
+            #[cfg(feature = "debug")]
+            debug!(
+                state = ?animation_graph.get_state(animation.current_vertex),
+                loop_count = animation.loop_count,
+                "looping animation segment"
+            );
+        } else {
+            // this segment has finished, transition to the next vertex on the path to desired_vertex
+            animation.finished_vertex = Some(animation.current_vertex);
+            // the desired state may play one of its variants rather than looping.
+            if animation.next_vertex() == animation.current_vertex {
+                animation.choose_variant(animation_graph);
+                transition_data = animation_graph
+                    .get_transition_data((animation.current_vertex, animation.next_vertex()));
             }
+            #[cfg(feature = "debug")]
+            let previous_vertex = animation.current_vertex;
+            // the current frame has just finished playing.
+            let frame = transition_data.to_frame(
+                (animation.frame + 1) as f32 / segment_length as f32,
+                animation_graph
+                    .get_segment_data(animation.next_vertex())
+                    .segment_frames
+                    .len(),
+            );
+            animation.take_transition(frame);
+
+            #[cfg(feature = "debug")]
+            debug!(
+                from = ?animation_graph.get_state(previous_vertex),
+                to = ?animation_graph.get_state(animation.current_vertex),
+                frame = animation.frame,
+                "waiting animation transition"
+            );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{segment_data, State};
+    use crate::{SpriteAnimationGraphBuilder, TransitionData};
+
+    /// A to C either through B or (with a lower priority) through D.
+    fn animation_graph() -> SpriteAnimationGraph<State> {
+        return SpriteAnimationGraphBuilder::new()
+            .state(State::A, segment_data())
+            .state(State::B, segment_data())
+            .state(State::C, segment_data())
+            .state(State::D, segment_data())
+            .transition(State::A, State::A, TransitionData::wait())
+            .transition(State::A, State::B, TransitionData::wait().with_priority(1))
+            .transition(State::A, State::D, TransitionData::wait())
+            .transition(State::B, State::C, TransitionData::wait())
+            .transition(State::D, State::C, TransitionData::wait())
+            .transition(State::C, State::C, TransitionData::wait())
+            .transition(State::D, State::D, TransitionData::wait())
+            .build()
+            .unwrap();
+    }
+
+    fn world(animation_graph: SpriteAnimationGraph<State>) -> (World, Schedule) {
+        let mut world = World::new();
+        world.insert_resource(animation_graph);
+        world.insert_resource(Time::default());
+        world.init_resource::<Events<AnimationPathError<State>>>();
+        let mut schedule = Schedule::new();
+        schedule.add_systems((plan_paths::<State>, advance_frames::<State>).chain());
+        return (world, schedule);
+    }
+
+    fn spawn(world: &mut World, current: State, desired: State) -> Entity {
+        let vertex = world
+            .resource::<SpriteAnimationGraph<State>>()
+            .get_vertex(&current);
+        return world
+            .spawn((desired, SpriteAnimation::new(vertex, 1.)))
+            .id();
+    }
+
+    fn current_state(world: &World, entity: Entity) -> State {
+        let animation_graph = world.resource::<SpriteAnimationGraph<State>>();
+        return *world
+            .get::<SpriteAnimation>(entity)
+            .unwrap()
+            .current_state(animation_graph);
+    }
+
+    fn planned_path(world: &World, entity: Entity) -> Vec<State> {
+        let animation_graph = world.resource::<SpriteAnimationGraph<State>>();
+        return world
+            .get::<SpriteAnimation>(entity)
+            .unwrap()
+            .planned_path(animation_graph);
+    }
+
+    #[test]
+    fn plans_the_path_to_the_desired_state() {
+        let (mut world, mut schedule) = world(animation_graph());
+        let entity = spawn(&mut world, State::A, State::C);
+        schedule.run(&mut world);
+        assert_eq!(current_state(&world, entity), State::A);
+        assert_eq!(planned_path(&world, entity), vec![State::B, State::C]);
+    }
+
+    #[test]
+    fn animations_added_after_a_removal_use_the_current_vertices() {
+        let mut animation_graph = animation_graph();
+        animation_graph.remove_state(&State::A);
+        let (mut world, mut schedule) = world(animation_graph);
+        let entity = spawn(&mut world, State::C, State::C);
+        schedule.run(&mut world);
+        assert_eq!(current_state(&world, entity), State::C);
+    }
+
+    #[test]
+    fn removing_a_transition_replans_paths_through_it() {
+        let (mut world, mut schedule) = world(animation_graph());
+        let entity = spawn(&mut world, State::A, State::C);
+        schedule.run(&mut world);
+
+        let mut animation_graph = world.resource_mut::<SpriteAnimationGraph<State>>();
+        let (b, c) = (
+            animation_graph.get_vertex(&State::B),
+            animation_graph.get_vertex(&State::C),
+        );
+        animation_graph.remove_transition((b, c));
+        schedule.run(&mut world);
+        assert_eq!(current_state(&world, entity), State::A);
+        assert_eq!(planned_path(&world, entity), vec![State::D, State::C]);
+    }
+
+    #[test]
+    fn removing_the_current_state_restarts_at_the_desired_state() {
+        let (mut world, mut schedule) = world(animation_graph());
+        let entity = spawn(&mut world, State::B, State::C);
+        schedule.run(&mut world);
+
+        world
+            .resource_mut::<SpriteAnimationGraph<State>>()
+            .remove_state(&State::B);
+        schedule.run(&mut world);
+        assert_eq!(current_state(&world, entity), State::C);
+        assert_eq!(planned_path(&world, entity), vec![State::C]);
+    }
+
+    #[test]
+    fn replacing_the_graph_with_an_older_revision_replans() {
+        let mut edited_graph = animation_graph();
+        edited_graph.remove_state(&State::D);
+        let (mut world, mut schedule) = world(edited_graph);
+        let entity = spawn(&mut world, State::B, State::C);
+        schedule.run(&mut world);
+
+        // a freshly built graph has not been changed yet, the animation's vertices are stale.
+        world.insert_resource(animation_graph());
+        schedule.run(&mut world);
+        assert_eq!(current_state(&world, entity), State::C);
+        assert_eq!(planned_path(&world, entity), vec![State::C]);
+    }
+
+    #[test]
+    fn loaded_animations_restart_at_the_desired_state() {
+        use bevy::reflect::FromReflect;
+
+        // an animation saved in B before A was removed from the graph, B is now the vertex of C.
+        let (mut world, mut schedule) = world(animation_graph());
+        let entity = spawn(&mut world, State::B, State::C);
+        schedule.run(&mut world);
+        let loaded =
+            SpriteAnimation::from_reflect(world.get::<SpriteAnimation>(entity).unwrap()).unwrap();
+
+        world
+            .resource_mut::<SpriteAnimationGraph<State>>()
+            .remove_state(&State::A);
+        let entity = world.spawn((State::D, loaded)).id();
+        schedule.run(&mut world);
+        assert_eq!(current_state(&world, entity), State::D);
+        assert_eq!(planned_path(&world, entity), vec![State::D]);
+    }
+}
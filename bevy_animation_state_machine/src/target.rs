@@ -0,0 +1,103 @@
+use bevy::ecs::query::{QueryItem, WorldQuery};
+use bevy::prelude::*;
+
+use crate::animation_graph::{Frame, SegmentData};
+use crate::SpriteAnimationState;
+
+/// Shows the current frame of an animation on an entity. The animation graph, path finding and frame timing are the same for every target,
+/// a target only decides what a frame of a segment looks like. [`SpriteTarget`] shows frames on sprites and UI images, other targets may
+/// for example play an `AnimationPlayer` clip or set material parameters for each state.
+///
+/// ```ignore
+/// struct ClipTarget;
+///
+/// impl AnimationTarget<PlayerState> for ClipTarget {
+///     type Query = (&'static mut AnimationPlayer, &'static PlayerClips);
+///
+///     fn apply((mut player, clips): QueryItem<Self::Query>, state: &PlayerState, _: &SegmentData, _: usize) {
+///         player.play(clips.get(state).clone_weak()).repeat();
+///     }
+/// }
+///
+/// app.add_plugins(AnimationTargetPlugin::<PlayerState, ClipTarget>::default());
+/// ```
+///
+/// Targets are applied whenever the state or frame of an animation changes, they should avoid writing components that already show the frame
+/// to avoid triggering change detection. Entities are applied in parallel.
+pub trait AnimationTarget<S: SpriteAnimationState>: Send + Sync + 'static {
+    /// the components of an animated entity that show its frames.
+    type Query: WorldQuery;
+
+    /// shows `frame` of the segment played by the animation state `state`.
+    fn apply(target: QueryItem<Self::Query>, state: &S, segment_data: &SegmentData, frame: usize);
+}
+
+/// Shows frames on sprites ([`TextureAtlasSprite`] or [`Handle<Image>`]) and UI images ([`UiTextureAtlasImage`] or [`UiImage`]),
+/// this is the target of the [`crate::SpriteAnimationPlugin`].
+pub struct SpriteTarget;
+
+impl<S: SpriteAnimationState> AnimationTarget<S> for SpriteTarget {
+    type Query = (
+        Option<&'static mut TextureAtlasSprite>,
+        Option<&'static mut UiTextureAtlasImage>,
+        Option<&'static mut Handle<TextureAtlas>>,
+        Option<&'static mut Handle<Image>>,
+        Option<&'static mut UiImage>,
+    );
+
+    fn apply(
+        (sprite, ui_atlas_image, atlas, image, ui_image): QueryItem<Self::Query>,
+        _state: &S,
+        segment_data: &SegmentData,
+        frame: usize,
+    ) {
+        // the texture may change with every frame, it is only written if it differs to avoid triggering change detection.
+        // segments that are waiting for their texture atlas to load have nothing to show yet.
+        let Some(frame) = segment_data.frame(frame) else {
+            return;
+        };
+        match frame {
+            Frame::Atlas(handle, index) => {
+                // the atlas index is shown by either a sprite or a UI image.
+                let atlas_index = match (sprite, ui_atlas_image) {
+                    (Some(sprite), _) => Some(sprite.map_unchanged(|sprite| &mut sprite.index)),
+                    (None, Some(ui_atlas_image)) => Some(
+                        ui_atlas_image.map_unchanged(|ui_atlas_image| &mut ui_atlas_image.index),
+                    ),
+                    (None, None) => None,
+                };
+                // entities without a texture atlas cannot show this frame
+                if let (Some(mut atlas_index), Some(mut atlas)) = (atlas_index, atlas) {
+                    if *atlas != *handle {
+                        *atlas = handle.clone();
+
+                        #[cfg(feature = "debug")]
+                        trace!(state = ?_state, atlas = ?atlas.id(), "swapped texture atlas");
+                    }
+                    if *atlas_index != index {
+                        *atlas_index = index;
+                    }
+                }
+            }
+            Frame::Image(handle) => {
+                // the image is shown by either a sprite or a UI image.
+                let image = match (image, ui_image) {
+                    (Some(image), _) => Some(image),
+                    (None, Some(ui_image)) => {
+                        Some(ui_image.map_unchanged(|ui_image| &mut ui_image.texture))
+                    }
+                    (None, None) => None,
+                };
+                // entities without an image cannot show this frame
+                if let Some(mut image) = image {
+                    if *image != *handle {
+                        *image = handle.clone();
+
+                        #[cfg(feature = "debug")]
+                        trace!(state = ?_state, image = ?image.id(), "swapped image");
+                    }
+                }
+            }
+        }
+    }
+}
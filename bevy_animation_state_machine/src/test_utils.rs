@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+use crate::{SegmentBehaviour, SegmentData, SegmentInterval};
+
+/// the states used by the unit tests.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+pub(crate) enum State {
+    A,
+    B,
+    C,
+    D,
+}
+
+/// a forward segment of four frames.
+pub(crate) fn segment_data() -> SegmentData {
+    return SegmentData {
+        segment_frames: SegmentInterval::new(0, 4).into(),
+        segment_behaviour: SegmentBehaviour::Forward,
+        texture_atlas_handle: Handle::default(),
+    };
+}
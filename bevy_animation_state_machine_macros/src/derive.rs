@@ -0,0 +1,170 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::{parse_quote, Data, DeriveInput, Error, Expr, Fields, Ident, Token, Type};
+
+use crate::graph::{GraphDef, LoopsDef, SegmentDef, StateDef, TransitionDef, TransitionKind};
+
+pub(crate) fn derive_sprite_animation_graph(input: DeriveInput) -> Result<TokenStream, Error> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`SpriteAnimationGraph` can only be derived for enums",
+        ));
+    };
+
+    let mut atlases: Option<Type> = None;
+    for attr in &input.attrs {
+        if attr.path().is_ident("sprite_animation") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("atlases") {
+                    atlases = Some(meta.value()?.parse()?);
+                    return Ok(());
+                }
+                Err(meta.error("expected `atlases = <resource type>`"))
+            })?;
+        }
+    }
+    let Some(atlases) = atlases else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "missing `#[sprite_animation(atlases = <resource type>)]`, the resource that holds the texture atlas of each segment",
+        ));
+    };
+
+    let mut graph = GraphDef {
+        states: Vec::new(),
+        transitions: Vec::new(),
+    };
+    for variant in &data.variants {
+        let mut segment = None;
+        for attr in &variant.attrs {
+            if attr.path().is_ident("segment") {
+                segment = Some(SegmentDef::parse(
+                    attr,
+                    attr.meta.require_list()?.tokens.clone(),
+                )?);
+            } else if attr.path().is_ident("transition") {
+                graph
+                    .transitions
+                    .push(parse_transition(&variant.ident, attr)?);
+            }
+        }
+        if let Some(segment) = segment {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new_spanned(
+                    &variant.fields,
+                    "animation states must be unit variants",
+                ));
+            }
+            graph.states.push(StateDef {
+                variant: variant.ident.clone(),
+                segment,
+            });
+        }
+    }
+    graph.validate()?;
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let body = graph.expand(&parse_quote!(Self), &parse_quote!(atlases));
+    Ok(quote! {
+        impl #impl_generics #ident #type_generics #where_clause {
+            /// Creates the animation graph declared by the `#[segment]` and `#[transition]` attributes of this enum.
+            pub fn animation_graph(
+                atlases: &#atlases,
+            ) -> ::bevy_animation_state_machine::SpriteAnimationGraph<Self> {
+                #body
+            }
+        }
+    })
+}
+
+/// parses `#[transition(to = <state>, ...)]` followed by one of `immediate`, `immediate_to = <frame>`, `wait`, `wait_for = <frame>` and/or `wait_to = <frame>`,
+/// and optionally `priority = <priority>`, `sync_phase` or `sync_phase = <offset>`, `loops = <count>` or `loops = <min>..=<max>` and `weight = <weight>`. `wait_for` may also be a fraction of the segment (`0.5`) or a range of frames (`2..=4`).
+fn parse_transition(from: &Ident, attr: &syn::Attribute) -> Result<TransitionDef, Error> {
+    let mut to: Option<Ident> = None;
+    let mut kind: Option<TransitionKind> = None;
+    let mut priority: Option<Expr> = None;
+    let mut sync_phase: Option<Expr> = None;
+    let mut loops: Option<LoopsDef> = None;
+    let mut weight: Option<Expr> = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("to") {
+            to = Some(meta.value()?.parse()?);
+            return Ok(());
+        }
+        if meta.path.is_ident("priority") {
+            priority = Some(meta.value()?.parse()?);
+            return Ok(());
+        }
+        if meta.path.is_ident("weight") {
+            weight = Some(meta.value()?.parse()?);
+            return Ok(());
+        }
+        if meta.path.is_ident("loops") {
+            loops = Some(meta.value()?.parse()?);
+            return Ok(());
+        }
+        if meta.path.is_ident("sync_phase") {
+            // a bare `sync_phase` keeps the phase without an offset
+            sync_phase = Some(if meta.input.peek(Token![=]) {
+                meta.value()?.parse()?
+            } else {
+                parse_quote!(0.)
+            });
+            return Ok(());
+        }
+        parse_transition_kind(&meta, &mut kind)
+    })?;
+    Ok(TransitionDef {
+        from: from.clone(),
+        to: to.ok_or_else(|| Error::new_spanned(attr, "missing `to = <state>`"))?,
+        kind: kind.ok_or_else(|| {
+            Error::new_spanned(
+                attr,
+                "missing transition behaviour, expected `immediate`, `immediate_to`, `wait`, `wait_for` or `wait_to`",
+            )
+        })?,
+        priority,
+        sync_phase,
+        loops,
+        weight,
+    })
+}
+
+fn parse_transition_kind(
+    meta: &ParseNestedMeta,
+    kind: &mut Option<TransitionKind>,
+) -> Result<(), Error> {
+    let mixed = || meta.error("a transition cannot both be immediate and wait");
+    if meta.path.is_ident("immediate") || meta.path.is_ident("immediate_to") {
+        let to_frame = if meta.path.is_ident("immediate_to") {
+            Some(meta.value()?.parse()?)
+        } else {
+            None
+        };
+        if kind.is_some() {
+            return Err(mixed());
+        }
+        *kind = Some(TransitionKind::Immediate { to_frame });
+    } else if meta.path.is_ident("wait")
+        || meta.path.is_ident("wait_for")
+        || meta.path.is_ident("wait_to")
+    {
+        let (mut wait_for, mut wait_to) = match kind {
+            None => (None, None),
+            Some(TransitionKind::Wait { wait_for, wait_to }) => (wait_for.take(), wait_to.take()),
+            Some(TransitionKind::Immediate { .. }) => return Err(mixed()),
+        };
+        if meta.path.is_ident("wait_for") {
+            wait_for = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("wait_to") {
+            wait_to = Some(meta.value()?.parse()?);
+        }
+        *kind = Some(TransitionKind::Wait { wait_for, wait_to });
+    } else {
+        return Err(meta.error("unknown transition property"));
+    }
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use proc_macro2::TokenStream;
+use syn::parse::{Parse, ParseStream};
+use syn::{parenthesized, Error, Expr, Ident, Path, Token};
+
+use crate::graph::{
+    GraphDef, LoopsDef, SegmentDef, StateDef, TransitionDef, TransitionKind, WaitFor,
+};
+
+/// the input of `animation_graph!`: the state type and atlas resource followed by state and transition declarations.
+pub(crate) struct AnimationGraphInput {
+    state: Path,
+    atlases: Expr,
+    graph: GraphDef,
+}
+
+impl Parse for AnimationGraphInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let state: Path = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let atlases: Expr = input.parse()?;
+        input.parse::<Token![;]>()?;
+
+        let mut graph = GraphDef {
+            states: Vec::new(),
+            transitions: Vec::new(),
+        };
+        while !input.is_empty() {
+            let from: Ident = input.parse()?;
+            if input.peek(Token![->]) {
+                // <state> -> <state>: <transition>;
+                input.parse::<Token![->]>()?;
+                let to: Ident = input.parse()?;
+                input.parse::<Token![:]>()?;
+                let kind = parse_transition_kind(input)?;
+                // optionally followed by `.with_priority(<priority>)`, `.sync_phase(<offset>)`, `.with_loops(<loops>)` and/or `.with_weight(<weight>)`
+                let mut priority = None;
+                let mut sync_phase = None;
+                let mut loops = None;
+                let mut weight = None;
+                while input.peek(Token![.]) {
+                    input.parse::<Token![.]>()?;
+                    let method: Ident = input.parse()?;
+                    let duplicate = if method == "with_priority" {
+                        priority.is_some()
+                    } else if method == "sync_phase" {
+                        sync_phase.is_some()
+                    } else if method == "with_loops" {
+                        loops.is_some()
+                    } else if method == "with_weight" {
+                        weight.is_some()
+                    } else {
+                        return Err(Error::new(
+                            method.span(),
+                            "expected `with_priority(...)`, `sync_phase(...)`, `with_loops(...)` or `with_weight(...)`",
+                        ));
+                    };
+                    if duplicate {
+                        return Err(Error::new(method.span(), format!("duplicate `{}`", method)));
+                    }
+                    let content;
+                    parenthesized!(content in input);
+                    if method == "with_priority" {
+                        priority = Some(content.parse()?);
+                    } else if method == "sync_phase" {
+                        sync_phase = Some(content.parse()?);
+                    } else if method == "with_loops" {
+                        loops = Some(content.parse::<LoopsDef>()?);
+                    } else {
+                        weight = Some(content.parse()?);
+                    }
+                }
+                graph.transitions.push(TransitionDef {
+                    from,
+                    to,
+                    kind,
+                    priority,
+                    sync_phase,
+                    loops,
+                    weight,
+                });
+            } else {
+                // <state>: segment(...);
+                input.parse::<Token![:]>()?;
+                let keyword: Ident = input.parse()?;
+                if keyword != "segment" {
+                    return Err(Error::new(keyword.span(), "expected `segment(...)`"));
+                }
+                let content;
+                parenthesized!(content in input);
+                let segment = SegmentDef::parse(&keyword, content.parse()?)?;
+                graph.states.push(StateDef {
+                    variant: from,
+                    segment,
+                });
+            }
+            input.parse::<Token![;]>()?;
+        }
+        Ok(AnimationGraphInput {
+            state,
+            atlases,
+            graph,
+        })
+    }
+}
+
+/// parses one of the `TransitionData` constructors `immediate`, `immediate_to(<frame>)`, `wait`, `wait_for(<frame>)`,
+/// `wait_to(<frame>)` or `wait_for_then_to(<frame>, <frame>)`, where the frame waited for may also be a fraction of the segment (`0.5`)
+/// or a range of frames (`2..=4`).
+fn parse_transition_kind(input: ParseStream) -> syn::Result<TransitionKind> {
+    let constructor: Ident = input.parse()?;
+    let arguments: Vec<WaitFor> = if input.peek(syn::token::Paren) {
+        let content;
+        parenthesized!(content in input);
+        content
+            .parse_terminated(WaitFor::parse, Token![,])?
+            .into_iter()
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let kind = match (constructor.to_string().as_str(), arguments.as_slice()) {
+        ("immediate", []) => TransitionKind::Immediate { to_frame: None },
+        ("immediate_to", [WaitFor::Frame(frame)]) => TransitionKind::Immediate {
+            to_frame: Some(frame.clone()),
+        },
+        ("wait", []) => TransitionKind::Wait {
+            wait_for: None,
+            wait_to: None,
+        },
+        ("wait_for", [wait_for]) => TransitionKind::Wait {
+            wait_for: Some(wait_for.clone()),
+            wait_to: None,
+        },
+        ("wait_to", [WaitFor::Frame(frame)]) => TransitionKind::Wait {
+            wait_for: None,
+            wait_to: Some(frame.clone()),
+        },
+        ("wait_for_then_to", [wait_for, WaitFor::Frame(to_frame)]) => TransitionKind::Wait {
+            wait_for: Some(wait_for.clone()),
+            wait_to: Some(to_frame.clone()),
+        },
+        _ => {
+            return Err(Error::new(
+                constructor.span(),
+                "expected one of `immediate`, `immediate_to(<frame>)`, `wait`, `wait_for(<frame>)`, `wait_to(<frame>)` or `wait_for_then_to(<frame>, <frame>)`",
+            ))
+        }
+    };
+    Ok(kind)
+}
+
+pub(crate) fn animation_graph(input: AnimationGraphInput) -> Result<TokenStream, Error> {
+    input.graph.validate()?;
+    Ok(input.graph.expand(&input.state, &input.atlases))
+}
@@ -0,0 +1,507 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::{Error, Expr, Ident, LitFloat, LitInt, Path, Token};
+
+/// a state of the animation graph together with the segment that is played while in it.
+pub(crate) struct StateDef {
+    pub variant: Ident,
+    pub segment: SegmentDef,
+}
+
+pub(crate) struct SegmentDef {
+    /// the field of the atlas resource that holds the texture atlas of this segment.
+    pub atlas: Ident,
+    pub frames: FramesDef,
+    /// a variant of `SegmentBehaviour`, defaults to `Forward`.
+    pub behaviour: Option<Ident>,
+}
+
+/// the `SegmentFrames` of a segment.
+pub(crate) enum FramesDef {
+    /// without a length, the segment continues to the end of the atlas.
+    Interval {
+        start: Option<LitInt>,
+        length: Option<LitInt>,
+    },
+    Indices(Vec<LitInt>),
+    Grid {
+        column: Option<LitInt>,
+        row: LitInt,
+        length: LitInt,
+    },
+}
+
+impl FramesDef {
+    /// the number of frames, or `None` if it is only known once the atlas has loaded.
+    fn len(&self) -> Result<Option<usize>, Error> {
+        match self {
+            FramesDef::Interval { length: None, .. } => Ok(None),
+            FramesDef::Interval {
+                length: Some(length),
+                ..
+            }
+            | FramesDef::Grid { length, .. } => length.base10_parse().map(Some),
+            FramesDef::Indices(indices) => Ok(Some(indices.len())),
+        }
+    }
+
+    fn expand(&self) -> TokenStream {
+        let crate_path = quote!(::bevy_animation_state_machine);
+        match self {
+            FramesDef::Interval { start, length } => {
+                let start = match start {
+                    Some(start) => quote!(#start),
+                    None => quote!(0),
+                };
+                match length {
+                    Some(length) => {
+                        quote!(#crate_path::SegmentFrames::Interval(#crate_path::SegmentInterval::new(#start, #length)))
+                    }
+                    None => quote!(#crate_path::SegmentFrames::Remaining { start: #start }),
+                }
+            }
+            FramesDef::Indices(indices) => {
+                quote!(#crate_path::SegmentFrames::Indices(vec![#(#indices),*]))
+            }
+            FramesDef::Grid {
+                column,
+                row,
+                length,
+            } => {
+                let column = match column {
+                    Some(column) => quote!(#column),
+                    None => quote!(0),
+                };
+                quote!(#crate_path::SegmentFrames::Grid(#crate_path::SegmentGrid::new(#column, #row, #length)))
+            }
+        }
+    }
+}
+
+impl SegmentDef {
+    /// parses `atlas = <field>, start = <frame>, length = <frames>, behaviour = <SegmentBehaviour>`, where `start` and `length`
+    /// may be replaced by a list of atlas indices `frames = [<index>, ...]`, and `start` may be replaced by a grid position
+    /// `row = <row>, column = <column>` (the column defaults to 0). Without a `length` or `frames`, the segment continues from `start`
+    /// to the end of the atlas. Errors for missing properties are reported at `spanned`.
+    pub fn parse(spanned: &impl ToTokens, tokens: TokenStream) -> Result<Self, Error> {
+        let mut atlas: Option<Ident> = None;
+        let mut start: Option<LitInt> = None;
+        let mut length: Option<LitInt> = None;
+        let mut indices: Option<Vec<LitInt>> = None;
+        let mut row: Option<LitInt> = None;
+        let mut column: Option<LitInt> = None;
+        let mut behaviour: Option<Ident> = None;
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("atlas") {
+                atlas = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("start") {
+                start = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("length") {
+                length = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("frames") {
+                let value = meta.value()?;
+                let content;
+                syn::bracketed!(content in value);
+                indices = Some(
+                    content
+                        .parse_terminated(|input| input.parse::<LitInt>(), syn::Token![,])?
+                        .into_iter()
+                        .collect(),
+                );
+            } else if meta.path.is_ident("row") {
+                row = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("column") {
+                column = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("behaviour") {
+                behaviour = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta
+                    .error("expected one of `atlas`, `start`, `length`, `frames`, `row`, `column` or `behaviour`"));
+            }
+            Ok(())
+        })
+        .parse2(tokens)?;
+        if column.is_some() && row.is_none() {
+            return Err(Error::new_spanned(
+                spanned,
+                "`column` must be used together with `row`",
+            ));
+        }
+        let frames = match (start, length, indices, row) {
+            (start, length, None, None) => FramesDef::Interval { start, length },
+            (None, Some(length), None, Some(row)) => FramesDef::Grid {
+                column,
+                row,
+                length,
+            },
+            (None, None, Some(indices), None) => FramesDef::Indices(indices),
+            (_, _, Some(_), _) => {
+                return Err(Error::new_spanned(
+                    spanned,
+                    "`frames` cannot be used together with `start`, `length` or `row`",
+                ))
+            }
+            (Some(_), _, None, Some(_)) => {
+                return Err(Error::new_spanned(
+                    spanned,
+                    "`start` cannot be used together with `row`",
+                ))
+            }
+            (None, None, None, Some(_)) => {
+                return Err(Error::new_spanned(
+                    spanned,
+                    "missing `length = <frames>`, it is required with `row`",
+                ))
+            }
+        };
+        Ok(SegmentDef {
+            atlas: atlas.ok_or_else(|| Error::new_spanned(spanned, "missing `atlas = <field>`"))?,
+            frames,
+            behaviour,
+        })
+    }
+}
+
+pub(crate) struct TransitionDef {
+    pub from: Ident,
+    pub to: Ident,
+    pub kind: TransitionKind,
+    /// used to break ties between equally short paths, defaults to 0.
+    pub priority: Option<Expr>,
+    /// the phase offset of a transition that keeps the outgoing segment's progress, if any.
+    pub sync_phase: Option<Expr>,
+    /// the number of times the segment plays before the transition is taken, if more than once.
+    pub loops: Option<LoopsDef>,
+    /// the weight of choosing the transition at random when its state loops, if any.
+    pub weight: Option<Expr>,
+}
+
+pub(crate) enum TransitionKind {
+    Immediate {
+        to_frame: Option<LitInt>,
+    },
+    Wait {
+        wait_for: Option<WaitFor>,
+        wait_to: Option<LitInt>,
+    },
+}
+
+/// what a waiting transition waits for: a frame `<frame>`, a fraction of the segment `<fraction>` (a float literal) or any frame
+/// in a range `<first>..=<last>`.
+#[derive(Clone)]
+pub(crate) enum WaitFor {
+    Frame(LitInt),
+    Fraction(LitFloat),
+    Range(LitInt, LitInt),
+}
+
+impl Parse for WaitFor {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitFloat) {
+            return Ok(WaitFor::Fraction(input.parse()?));
+        }
+        let frame: LitInt = input.parse()?;
+        if input.peek(Token![..=]) {
+            input.parse::<Token![..=]>()?;
+            return Ok(WaitFor::Range(frame, input.parse()?));
+        }
+        Ok(WaitFor::Frame(frame))
+    }
+}
+
+impl WaitFor {
+    /// checks that fractions are between 0 and 1 and that ranges are not empty.
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            WaitFor::Frame(_) => Ok(()),
+            WaitFor::Fraction(fraction) => {
+                let value: f32 = fraction.base10_parse()?;
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(Error::new(
+                        fraction.span(),
+                        "the fraction of the segment to wait for must be between 0 and 1",
+                    ));
+                }
+                Ok(())
+            }
+            WaitFor::Range(first, last) => {
+                if first.base10_parse::<usize>()? > last.base10_parse::<usize>()? {
+                    return Err(Error::new(
+                        last.span(),
+                        "the range of frames to wait for is empty",
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// expands to the `TransitionData` constructor that waits for this.
+    fn expand(&self) -> TokenStream {
+        let data = quote!(::bevy_animation_state_machine::TransitionData);
+        match self {
+            WaitFor::Frame(frame) => quote!(#data::wait_for(#frame)),
+            WaitFor::Fraction(fraction) => quote!(#data::wait_for_fraction(#fraction)),
+            WaitFor::Range(first, last) => quote!(#data::wait_for_range(#first..=#last)),
+        }
+    }
+}
+
+/// the number of times a segment plays before a waiting transition is taken: `<count>` or a random count in a range `<min>..=<max>`.
+pub(crate) struct LoopsDef {
+    min: LitInt,
+    max: Option<LitInt>,
+}
+
+impl Parse for LoopsDef {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let min: LitInt = input.parse()?;
+        if input.peek(Token![..=]) {
+            input.parse::<Token![..=]>()?;
+            return Ok(LoopsDef {
+                min,
+                max: Some(input.parse()?),
+            });
+        }
+        Ok(LoopsDef { min, max: None })
+    }
+}
+
+impl LoopsDef {
+    /// checks that the segment plays at least once and that ranges are not empty.
+    fn validate(&self) -> Result<(), Error> {
+        let min: usize = self.min.base10_parse()?;
+        if min == 0 {
+            return Err(Error::new(
+                self.min.span(),
+                "a segment must play at least once",
+            ));
+        }
+        if let Some(max) = &self.max {
+            if min > max.base10_parse::<usize>()? {
+                return Err(Error::new(max.span(), "the range of loops is empty"));
+            }
+        }
+        Ok(())
+    }
+
+    fn expand(&self) -> TokenStream {
+        let min = &self.min;
+        match &self.max {
+            Some(max) => quote!(#min..=#max),
+            None => quote!(#min),
+        }
+    }
+}
+
+/// an animation graph declared on a state enum.
+pub(crate) struct GraphDef {
+    pub states: Vec<StateDef>,
+    pub transitions: Vec<TransitionDef>,
+}
+
+impl GraphDef {
+    /// checks everything that `SpriteAnimationGraph::add_transition` would otherwise only report at runtime, combining all problems into a single error.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut errors: Vec<Error> = Vec::new();
+
+        let mut states = HashSet::new();
+        for state in &self.states {
+            if !states.insert(&state.variant) {
+                errors.push(Error::new(
+                    state.variant.span(),
+                    format!("duplicate animation state `{}`", state.variant),
+                ));
+            }
+        }
+
+        let mut edges = HashSet::new();
+        for transition in &self.transitions {
+            for state in [&transition.from, &transition.to] {
+                if !states.contains(state) {
+                    errors.push(Error::new(
+                        state.span(),
+                        format!("`{}` is not a state of the animation graph", state),
+                    ));
+                }
+            }
+            if !edges.insert((&transition.from, &transition.to)) {
+                errors.push(Error::new(
+                    transition.to.span(),
+                    format!(
+                        "duplicate transition `{} -> {}`",
+                        transition.from, transition.to
+                    ),
+                ));
+            }
+            if let TransitionKind::Wait {
+                wait_for: Some(wait_for),
+                ..
+            } = &transition.kind
+            {
+                if let Err(error) = wait_for.validate() {
+                    errors.push(error);
+                }
+            }
+            if let Some(loops) = &transition.loops {
+                if let TransitionKind::Immediate { .. } = transition.kind {
+                    errors.push(Error::new(
+                        loops.min.span(),
+                        "loops can only be used by waiting transitions",
+                    ));
+                } else if let Err(error) = loops.validate() {
+                    errors.push(error);
+                }
+            }
+            if let TransitionKind::Immediate { .. } = transition.kind {
+                if transition.from == transition.to {
+                    errors.push(Error::new(
+                        transition.to.span(),
+                        format!(
+                            "immediate transitions cannot be used in a self-transition `{} -> {}`",
+                            transition.from, transition.to
+                        ),
+                    ));
+                }
+            }
+            if let (Some(to_frame), Some(to)) = (
+                transition.kind.to_frame(),
+                self.states
+                    .iter()
+                    .find(|state| state.variant == transition.to),
+            ) {
+                let frame: usize = to_frame.base10_parse()?;
+                // the length of segments without one is only known (and checked) once their atlas has loaded.
+                let length = to.segment.frames.len()?.unwrap_or(usize::MAX);
+                if frame >= length {
+                    errors.push(Error::new(
+                        to_frame.span(),
+                        format!(
+                            "frame {} is out of range, the segment of `{}` has {} frames",
+                            frame, to.variant, length
+                        ),
+                    ));
+                }
+            }
+        }
+
+        match errors.into_iter().reduce(|mut combined, error| {
+            combined.combine(error);
+            combined
+        }) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// expands to an expression that creates the `SpriteAnimationGraph<state>`, taking atlas handles from the fields of `atlases`.
+    pub fn expand(&self, state: &Path, atlases: &Expr) -> TokenStream {
+        let vertex = |variant: &Ident| {
+            let index = self
+                .states
+                .iter()
+                .position(|state| &state.variant == variant)
+                .unwrap();
+            format_ident!("vertex_{}", index)
+        };
+
+        let states = self.states.iter().map(|def| {
+            let variant = &def.variant;
+            let v = vertex(variant);
+            let atlas = &def.segment.atlas;
+            let frames = def.segment.frames.expand();
+            let behaviour = match &def.segment.behaviour {
+                Some(behaviour) => quote!(#behaviour),
+                None => quote!(Forward),
+            };
+            quote! {
+                let #v = animation_graph.add_state(
+                    #state::#variant,
+                    ::bevy_animation_state_machine::SegmentData {
+                        segment_frames: #frames,
+                        segment_behaviour: ::bevy_animation_state_machine::SegmentBehaviour::#behaviour,
+                        texture_atlas_handle: atlases.#atlas.clone(),
+                    },
+                );
+            }
+        });
+
+        let transitions = self.transitions.iter().map(|def| {
+            let from = vertex(&def.from);
+            let to = vertex(&def.to);
+            let mut data = def.kind.expand();
+            if let Some(priority) = &def.priority {
+                data = quote!(#data.with_priority(#priority));
+            }
+            if let Some(offset) = &def.sync_phase {
+                data = quote!(#data.sync_phase(#offset));
+            }
+            if let Some(loops) = &def.loops {
+                let loops = loops.expand();
+                data = quote!(#data.with_loops(#loops));
+            }
+            if let Some(weight) = &def.weight {
+                data = quote!(#data.with_weight(#weight));
+            }
+            quote!(((#from, #to), #data))
+        });
+
+        quote! {
+            {
+                let atlases = #atlases;
+                let mut animation_graph =
+                    ::bevy_animation_state_machine::SpriteAnimationGraph::<#state>::new();
+                #(#states)*
+                animation_graph.add_transitions([#(#transitions),*]);
+                animation_graph
+            }
+        }
+    }
+}
+
+impl TransitionKind {
+    /// the frame of the next segment that the transition goes to, if given.
+    fn to_frame(&self) -> Option<&LitInt> {
+        match self {
+            TransitionKind::Immediate { to_frame } => to_frame.as_ref(),
+            TransitionKind::Wait { wait_to, .. } => wait_to.as_ref(),
+        }
+    }
+
+    /// expands to the `TransitionData` constructor matching this transition.
+    fn expand(&self) -> TokenStream {
+        let data = quote!(::bevy_animation_state_machine::TransitionData);
+        match self {
+            TransitionKind::Immediate { to_frame: None } => quote!(#data::immediate()),
+            TransitionKind::Immediate {
+                to_frame: Some(frame),
+            } => quote!(#data::immediate_to(#frame)),
+            TransitionKind::Wait {
+                wait_for: None,
+                wait_to: None,
+            } => quote!(#data::wait()),
+            TransitionKind::Wait {
+                wait_for: Some(wait_for),
+                wait_to: None,
+            } => wait_for.expand(),
+            TransitionKind::Wait {
+                wait_for: None,
+                wait_to: Some(frame),
+            } => quote!(#data::wait_to(#frame)),
+            TransitionKind::Wait {
+                wait_for: Some(WaitFor::Frame(wait_frame)),
+                wait_to: Some(to_frame),
+            } => quote!(#data::wait_for_then_to(#wait_frame, #to_frame)),
+            TransitionKind::Wait {
+                wait_for: Some(wait_for),
+                wait_to: Some(to_frame),
+            } => {
+                let data = wait_for.expand();
+                quote!(#data.then_to(#to_frame))
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+
+mod derive;
+mod dsl;
+mod graph;
+
+/// Derives `animation_graph(atlases: &A) -> SpriteAnimationGraph<Self>` for an animation state enum.
+///
+/// The atlas resource is given on the enum with `#[sprite_animation(atlases = A)]`. Every variant that is part of the graph
+/// declares its segment with `#[segment(atlas = <field of A>, start = <frame>, length = <frames>, behaviour = <SegmentBehaviour>)]`
+/// (`start`, `length` and `behaviour` are optional, without a `length` the segment continues to the end of the atlas; `start` and `length`
+/// may be replaced by a list of atlas indices `frames = [<index>, ...]`, and `start` may be replaced by a position in a grid atlas
+/// `row = <row>, column = <column>` where `column` is optional)
+/// and its outgoing transitions with `#[transition(to = <variant>, ...)]`, where the transition behaviour is one of `immediate`,
+/// `immediate_to = <frame>`, `wait`, `wait_for = <frame>` and/or `wait_to = <frame>`. Rather than a frame, `wait_for` may be given a fraction
+/// of the segment (e.g. `wait_for = 0.5`) or a range of frames (e.g. `wait_for = 2..=4`).
+/// Transitions may also be given a `priority = <priority>` to break ties between equally short paths, and `sync_phase` (or `sync_phase = <offset>`)
+/// to continue the next segment at the same fraction that the current segment has played, e.g. between walking and running cycles.
+/// Waiting transitions may be given `loops = <count>` (or a random count `loops = <min>..=<max>`) to play the segment that many times before they are taken,
+/// and any transition may be given a `weight = <weight>` to be taken at random (followed by a return to its state) each time its state loops, e.g. for idle fidgets.
+///
+/// Unknown states, duplicate transitions, immediate self-transitions and out of range frames are reported at compile time.
+#[proc_macro_derive(
+    SpriteAnimationGraph,
+    attributes(sprite_animation, segment, transition)
+)]
+pub fn derive_sprite_animation_graph(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive::derive_sprite_animation_graph(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// Declares a `SpriteAnimationGraph` as a list of states and transitions, an alternative to deriving `SpriteAnimationGraph`.
+///
+/// The first line names the animation state type and the atlas resource (an expression) that segments take their texture atlases from.
+/// States are declared with the same properties as the `#[segment(...)]` attribute and transitions with the name of the `TransitionData` constructor to use,
+/// optionally followed by `.with_priority(<priority>)`, `.sync_phase(<offset>)`, `.with_loops(<loops>)` and/or `.with_weight(<weight>)`.
+///
+/// ```ignore
+/// let animation_graph = animation_graph! {
+///     PlayerState, world.resource::<PlayerAtlasResource>();
+///     Idle: segment(atlas = idle, length = 10);
+///     Walking: segment(atlas = walking, length = 8);
+///     Running: segment(atlas = running, length = 8);
+///     Idle -> Idle: wait;
+///     Idle -> Walking: wait.with_loops(2..=4);
+///     Idle -> Running: immediate_to(3);
+///     Running -> Running: wait;
+///     Running -> Idle: wait_for(3).with_priority(1);
+///     Walking -> Walking: wait;
+///     Walking -> Running: immediate.sync_phase(0.);
+/// };
+/// ```
+///
+/// Unknown states, duplicate transitions, immediate self-transitions and out of range frames are reported at compile time.
+#[proc_macro]
+pub fn animation_graph(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as dsl::AnimationGraphInput);
+    // the macro is used in expression position, where several `compile_error!` invocations have to be wrapped in a block.
+    dsl::animation_graph(input)
+        .unwrap_or_else(|error| {
+            let errors = error.to_compile_error();
+            quote!({ #errors })
+        })
+        .into()
+}
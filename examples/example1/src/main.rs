@@ -44,10 +44,11 @@ fn main() {
         .add_systems(OnEnter(GameState::Running), spawn_camera)
         .add_systems(OnEnter(GameState::Running), spawn_player)
         .add_systems(Update, bevy::window::close_on_esc)
+        // the animation system will start running once the animation graph has been created.
+        .add_plugins(SpriteAnimationPlugin::<PlayerState>::default())
         .add_systems(
             Update,
-            (animation::<PlayerState>, update_player_animation)
-                .run_if(in_state(GameState::Running)),
+            update_player_animation.run_if(in_state(GameState::Running)),
         )
         .run();
 }